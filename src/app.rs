@@ -1,41 +1,59 @@
 use crossterm::{
-    event::{self, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    event::{self, DisableBracketedPaste, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     terminal::{disable_raw_mode, Clear, ClearType},
     ExecutableCommand,
 };
-use log::info;
+use log::{error, info, warn};
 use ratatui::{prelude::Backend, widgets::TableState, Terminal};
 use std::{
     io::{self, stdout},
-    process, thread,
+    process,
     time::{Duration, Instant},
 };
 
 use crate::{
-    app_options::AppOptions,
+    app_options::{
+        AppOptions, TestType, CUSTOM_TIME_MAX, CUSTOM_TIME_MIN, CUSTOM_TIME_STEP, NUMBERS_PERCENT_MAX,
+        NUMBERS_PERCENT_STEP, ZIPF_EXPONENT_MAX, ZIPF_EXPONENT_MIN, ZIPF_EXPONENT_STEP,
+    },
+    cli::CliArgs,
+    custom_text::{list_custom_texts, save_pasted_text},
+    key_stats::{KeyStats, KeyStatsStore},
+    languages::{display_name, LanguageRegistry},
+    results::{personal_best, PbKey, ResultStore, TestResult, TestsStartedStore},
+    text_gen::{list_code_languages, supports_zipf},
     type_test::TypingTest,
-    ui::{draw_ui, tabs::SelectedTab},
+    ui::{draw_ui, tabs::SelectedTab, ProfileView, TypingView, WrappedLines},
 };
 
+// How long the loop waits for input before it redraws and checks the timers again
+const TICK_RATE: Duration = Duration::from_millis(100);
+
 #[derive(Default)]
 pub struct OptionsState {
     pub selected_option: usize,
     options_count: usize,
+    pub message: Option<String>, // why the last change was refused
+    pub zipf_unsupported: bool, // the word list of the selected test is not ordered by frequency
 }
 
 impl OptionsState {
     pub fn new() -> Self {
         Self {
             selected_option: 0,
-            options_count: 5, //TODO Make Sure This matches the listed amount of options in the front end
+            options_count: 24, //TODO Make Sure This matches the listed amount of options in the front end
+            message: None,
+            zipf_unsupported: false,
         }
     }
 
     pub fn next(&mut self) {
+        self.message = None;
         self.selected_option = (self.selected_option + 1) % self.options_count;
     }
 
     pub fn previous(&mut self) {
+        self.message = None;
         if self.selected_option > 0 {
             self.selected_option -= 1;
         } else {
@@ -54,34 +72,159 @@ pub enum AppState {
 
 pub struct App {
     pub options: AppOptions,
+    config_options: AppOptions, // as loaded from the config, without the command line settings
+    cli_args: CliArgs,
+    languages: LanguageRegistry,
     pub typing_test: TypingTest,
+    wrapped: WrappedLines, // goal text lines of the current test, kept between frames so only new text is wrapped
     pub state: AppState,
     selected_tab: SelectedTab,
     options_state: OptionsState,
     reset_test: bool,
+    result_store: Option<ResultStore>,
+    result_history: Vec<TestResult>,
+    tests_started_store: Option<TestsStartedStore>,
+    tests_started: usize, // counted when the first char is typed, so abandoned tests are included
+    personal_best: Option<i64>, // best wpm for the current test settings, without the current run
+    key_stats_store: Option<KeyStatsStore>,
+    key_stats: KeyStats, // errors per char and bigram over all tests
+    account_table_state: TableState,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let opt = AppOptions::new();
-        Self {
+    pub fn new(args: CliArgs) -> Self {
+        let config_options = AppOptions::load();
+        let mut opt = config_options.clone();
+        args.apply(&mut opt);
+        let languages = LanguageRegistry::discover();
+        if languages.get(&opt.test_language).is_none() {
+            if let Some(first) = languages.first() {
+                warn!("Language {} not found, using {}", opt.test_language, first.name);
+                opt.test_language = first.name.clone();
+            }
+        }
+        let result_store = ResultStore::open_default();
+        let result_history = result_store
+            .as_ref()
+            .map(|store| store.load_all())
+            .unwrap_or_default();
+        let tests_started_store = TestsStartedStore::open_default();
+        // results from before the count was kept are the best guess for it
+        let tests_started = tests_started_store
+            .as_ref()
+            .and_then(|store| store.load())
+            .unwrap_or(result_history.len());
+        let key_stats_store = KeyStatsStore::open_default();
+        let key_stats = key_stats_store
+            .as_ref()
+            .map(|store| store.load())
+            .unwrap_or_default();
+        let typing_test = TypingTest::new(&opt, &languages.resolve(&opt.test_language), &key_stats);
+        let wrapped = WrappedLines::new(opt.test_type == TestType::Code);
+        let mut app = Self {
             options: opt.clone(),
-            typing_test: TypingTest::new(opt.test_language.clone(), opt.test_type.clone()),
+            config_options,
+            cli_args: args,
+            languages,
+            typing_test,
+            wrapped,
             state: AppState::StartScreen,
             selected_tab: SelectedTab::Tab1,
             options_state: OptionsState::new(),
             reset_test: false,
-        }
+            result_store,
+            result_history,
+            tests_started_store,
+            tests_started,
+            personal_best: None,
+            key_stats_store,
+            key_stats,
+            account_table_state: TableState::default(),
+        };
+        app.refresh_personal_best();
+        app.refresh_zipf_support();
+        app
+    }
+
+    fn refresh_zipf_support(&mut self) {
+        let random_words = matches!(
+            self.options.test_type,
+            TestType::RandomWords | TestType::RandomWords1K | TestType::RandomWords10K
+        );
+        let language = self.languages.resolve(&self.options.test_language);
+        self.options_state.zipf_unsupported = random_words && !supports_zipf(&self.options, &language);
+    }
+
+    fn refresh_personal_best(&mut self) {
+        let key = PbKey::from_options(self.typing_test.options());
+        self.personal_best = personal_best(&self.result_history, &key);
     }
 
     fn start_new_test(&mut self) {
-        self.typing_test
-            .reset(self.options.test_language, self.options.test_type); // Reset Test
+        self.typing_test.reset(
+            &self.options,
+            &self.languages.resolve(&self.options.test_language),
+            &self.key_stats,
+        ); // Reset Test
+        self.wrapped = WrappedLines::new(self.options.test_type == TestType::Code);
+        self.refresh_personal_best();
         self.state = AppState::StartScreen; // Reset App-State
     }
 
-    fn handle_key_event(&mut self) -> Result<(), io::Error> {
-        if let event::Event::Key(key) = event::read()? {
+    /// Stop the test, store its result and move to the end screen.
+    fn finish_test(&mut self, completed: bool) {
+        self.typing_test.stop_timer();
+        self.typing_test.text_finished = completed;
+        self.typing_test.update_test_data();
+        self.state = AppState::EndScreen;
+
+        let result = TestResult::from_test(&self.typing_test);
+        if let Some(store) = &self.result_store {
+            match store.append(&result) {
+                Ok(()) => info!("Test result saved"),
+                Err(e) => error!("Could not save test result: {}", e),
+            }
+        }
+        // personal_best keeps the previous best until the next test, so the end screen can compare
+        self.result_history.push(result);
+
+        self.key_stats.merge(&self.typing_test.key_stats);
+        if let Some(store) = &self.key_stats_store {
+            if let Err(e) = store.save(&self.key_stats) {
+                error!("Could not save key stats: {}", e);
+            }
+        }
+    }
+
+    // saved right away, a test that is restarted or quit never gets to the end screen
+    fn count_started_test(&mut self) {
+        self.tests_started += 1;
+        if let Some(store) = &self.tests_started_store {
+            if let Err(e) = store.save(self.tests_started) {
+                error!("Could not save started tests: {}", e);
+            }
+        }
+    }
+
+    fn handle_key_event(&mut self, timeout: Duration) -> Result<(), io::Error> {
+        // Only block until the next tick, so timers keep running without input
+        if !event::poll(timeout)? {
+            // a combining mark would have come with the letter, the input on the last grapheme is final
+            if let AppState::RunningTest = self.state {
+                self.typing_test.resolve_pending();
+            }
+            return Ok(());
+        }
+
+        let event = event::read()?;
+        // pasted text becomes custom text, but only where it can't be mistaken for typing
+        if let event::Event::Paste(text) = &event {
+            if let SelectedTab::Tab2 = self.selected_tab {
+                self.use_pasted_text(text);
+            }
+        }
+
+        if let event::Event::Key(key) = event {
             // Gemeinsame Shortcuts für alle Tabs
             if key.kind == KeyEventKind::Press {
                 // Prüfe zuerst auf CONTROL-Kombinationen
@@ -90,8 +233,20 @@ impl App {
                         KeyCode::Char('q') => exit_app(),
                         KeyCode::Char('l') => self.next_tab(),
                         KeyCode::Right => self.next_tab(),
+                        // xterm and VTE send Ctrl+Backspace as ^H, which arrives as Ctrl+H,
+                        // so while typing it deletes a word and only Ctrl+Left switches tabs.
+                        // Terminals that send ^H for plain Backspace need the option turned off
+                        KeyCode::Char('h') if self.is_typing() && self.options.ctrl_h_deletes_word => {
+                            self.handle_typing_input(key)
+                        }
                         KeyCode::Char('h') => self.previous_tab(),
                         KeyCode::Left => self.previous_tab(),
+                        // word deletion while typing
+                        KeyCode::Backspace | KeyCode::Char('w') => {
+                            if let SelectedTab::Tab1 = self.selected_tab {
+                                self.handle_typing_input(key);
+                            }
+                        }
                         _ => {}
                     }
                 } else {
@@ -122,17 +277,23 @@ impl App {
                     (KeyCode::Char(c), KeyModifiers::NONE)
                     | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
                         self.typing_test.type_char(c);
-                        //if hardcore enabled
-                        if self.options.hardcore_enabled {
-                            //if wrong char has been tipped
-                            if self.typing_test.accuracy() != 100.0 {
-                                //end test
-                                self.typing_test.stop_timer();
-                                self.state = AppState::EndScreen;
-                            }
-                        }
+                        self.check_hardcore();
+                    }
+                    // line breaks and indentation are only part of code
+                    (KeyCode::Enter, KeyModifiers::NONE) if self.is_code_test() => {
+                        self.typing_test.type_char('\n');
+                        self.check_hardcore();
+                    }
+                    (KeyCode::Tab, KeyModifiers::NONE) if self.is_code_test() => {
+                        self.typing_test.type_tab();
+                        self.check_hardcore();
                     }
                     (KeyCode::Backspace, KeyModifiers::NONE) => self.typing_test.backspace(),
+                    // Ctrl+H is Ctrl+Backspace in terminals that send ^H for it, Ctrl+W deletes a word in shells
+                    (KeyCode::Backspace, KeyModifiers::CONTROL)
+                    | (KeyCode::Backspace, KeyModifiers::ALT)
+                    | (KeyCode::Char('h'), KeyModifiers::CONTROL)
+                    | (KeyCode::Char('w'), KeyModifiers::CONTROL) => self.typing_test.delete_word(),
                     _ => {}
                 }
             }
@@ -147,6 +308,27 @@ impl App {
         }
     }
 
+    // the typing tab with a test that takes input
+    fn is_typing(&self) -> bool {
+        matches!(self.selected_tab, SelectedTab::Tab1)
+            && matches!(self.state, AppState::StartScreen | AppState::RunningTest)
+    }
+
+    fn is_code_test(&self) -> bool {
+        matches!(self.typing_test.options().test_type, TestType::Code)
+    }
+
+    fn check_hardcore(&mut self) {
+        //if hardcore enabled
+        if self.options.hardcore_enabled {
+            //if wrong char has been tipped or a word skipped
+            if self.typing_test.accuracy() != 100.0 || self.typing_test.missed_chars > 0 {
+                //end test
+                self.finish_test(false);
+            }
+        }
+    }
+
     fn handle_options_input(&mut self, key: event::KeyEvent) {
         if key.modifiers == KeyModifiers::NONE {
             match key.code {
@@ -163,33 +345,84 @@ impl App {
         }
     }
 
-    fn handle_account_input(&mut self, key: event::KeyEvent) {}
+    fn handle_account_input(&mut self, key: event::KeyEvent) {
+        if key.modifiers == KeyModifiers::NONE {
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => self.account_table_state.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.account_table_state.select_next(),
+                KeyCode::Home => self.account_table_state.select_first(),
+                KeyCode::End => self.account_table_state.select_last(),
+                _ => {}
+            }
+        }
+    }
 
     fn handle_about_input(&mut self, key: event::KeyEvent) {}
 
     fn change_option_value(&mut self, increase: bool) {
+        self.options_state.message = None;
         match self.options_state.selected_option {
             0 => self.change_test_language(increase),
             1 => self.change_test_type(increase),
-            2 => self.options.time_race_enabled = !self.options.time_race_enabled, // Time Race
-            3 => self.options.hardcore_enabled = !self.options.hardcore_enabled,   // Hardcore
-            4 => self.change_ui_language(increase),                                // UI Language
+            2 => self.change_quote_length(increase),
+            3 => self.change_test_mode(increase),
+            4 => self.change_word_count(increase),
+            5 => self.change_time_race_duration(increase),
+            6 => self.change_custom_time(increase),
+            7 => self.options.hardcore_enabled = !self.options.hardcore_enabled,   // Hardcore
+            8 => self.toggle_lazy_mode(),                                          // Lazy Mode
+            9 => self.options.lenient_accents = !self.options.lenient_accents,     // Lenient Accents
+            10 => self.change_custom_text(increase),
+            11 => self.change_custom_text_mode(increase),
+            12 => self.options.custom_lowercase = !self.options.custom_lowercase,
+            13 => self.options.custom_strip_punctuation = !self.options.custom_strip_punctuation,
+            14 => self.change_punctuation(increase),
+            15 => self.change_numbers_percent(increase),
+            16 => self.change_word_sampling(increase),
+            17 => self.change_zipf_exponent(increase),
+            18 => self.change_code_language(increase),
+            19 => self.options.code_auto_indent = !self.options.code_auto_indent,
+            20 => self.toggle_seed(),
+            21 => self.change_backspace_policy(increase),
+            22 => self.options.ctrl_h_deletes_word = !self.options.ctrl_h_deletes_word,
+            23 => self.change_ui_language(increase),                               // UI Language
             _ => {}
         }
         //TODO dont reset test on every change
         //*-> change when returning to test screen , set flag here that test needs reset ->When returning to startscreen reset
         match self.options_state.selected_option {
-            0..=3 => self.reset_test = true,
+            0..=21 => self.reset_test = true,
             _ => {}
         }
+        self.refresh_zipf_support();
+        self.save_options();
+    }
+
+    // command line settings only last for this run, they don't end up in the config
+    fn save_options(&self) {
+        let mut options = self.options.clone();
+        self.cli_args.revert(&mut options, &self.config_options);
+        options.save();
     }
 
     fn change_test_language(&mut self, increase: bool) {
         if increase {
-            self.options.test_language = self.options.test_language.next();
+            self.options.test_language = self.languages.next(&self.options.test_language);
         } else {
-            self.options.test_language = self.options.test_language.previous();
+            self.options.test_language = self.languages.previous(&self.options.test_language);
+        }
+    }
+
+    fn toggle_lazy_mode(&mut self) {
+        let language = self.languages.resolve(&self.options.test_language);
+        if language.no_lazy_mode {
+            self.options_state.message = Some(format!(
+                "{} does not support lazy mode, its spelling has to stay as it is",
+                display_name(&language.name)
+            ));
+            return;
         }
+        self.options.toggle_lazy_mode();
     }
 
     fn change_test_type(&mut self, increase: bool) {
@@ -201,6 +434,163 @@ impl App {
         }
     }
 
+    fn change_quote_length(&mut self, increase: bool) {
+        if increase {
+            self.options.quote_length = self.options.quote_length.next();
+        } else {
+            self.options.quote_length = self.options.quote_length.previous();
+        }
+    }
+
+    fn change_test_mode(&mut self, increase: bool) {
+        if increase {
+            self.options.test_mode = self.options.test_mode.next();
+        } else {
+            self.options.test_mode = self.options.test_mode.previous();
+        }
+    }
+
+    fn change_word_count(&mut self, increase: bool) {
+        if increase {
+            self.options.word_count = self.options.word_count.next();
+        } else {
+            self.options.word_count = self.options.word_count.previous();
+        }
+    }
+
+    fn change_time_race_duration(&mut self, increase: bool) {
+        if increase {
+            self.options.time_race_duration = self.options.time_race_duration.next();
+        } else {
+            self.options.time_race_duration = self.options.time_race_duration.previous();
+        }
+    }
+
+    fn change_custom_time(&mut self, increase: bool) {
+        let secs = if increase {
+            self.options.custom_time_secs.saturating_add(CUSTOM_TIME_STEP)
+        } else {
+            self.options.custom_time_secs.saturating_sub(CUSTOM_TIME_STEP)
+        };
+        self.options.custom_time_secs = secs.clamp(CUSTOM_TIME_MIN, CUSTOM_TIME_MAX);
+    }
+
+    fn change_custom_text(&mut self, increase: bool) {
+        let mut texts = list_custom_texts();
+        // a file given on the command line stays selectable, even outside the texts directory
+        if let Some(current) = &self.options.custom_text_path {
+            if !texts.contains(current) {
+                texts.insert(0, current.clone());
+            }
+        }
+        if texts.is_empty() {
+            return;
+        }
+
+        let current = self
+            .options
+            .custom_text_path
+            .as_ref()
+            .and_then(|path| texts.iter().position(|text| text == path));
+        let index = match (current, increase) {
+            (None, _) => 0,
+            (Some(i), true) => (i + 1).min(texts.len() - 1),
+            (Some(i), false) => i.saturating_sub(1),
+        };
+        self.options.custom_text_path = Some(texts[index].clone());
+    }
+
+    fn change_custom_text_mode(&mut self, increase: bool) {
+        if increase {
+            self.options.custom_text_mode = self.options.custom_text_mode.next();
+        } else {
+            self.options.custom_text_mode = self.options.custom_text_mode.previous();
+        }
+    }
+
+    fn change_punctuation(&mut self, increase: bool) {
+        if increase {
+            self.options.punctuation = self.options.punctuation.next();
+        } else {
+            self.options.punctuation = self.options.punctuation.previous();
+        }
+    }
+
+    fn change_numbers_percent(&mut self, increase: bool) {
+        let percent = if increase {
+            self.options.numbers_percent.saturating_add(NUMBERS_PERCENT_STEP)
+        } else {
+            self.options.numbers_percent.saturating_sub(NUMBERS_PERCENT_STEP)
+        };
+        self.options.numbers_percent = percent.min(NUMBERS_PERCENT_MAX);
+    }
+
+    fn change_word_sampling(&mut self, increase: bool) {
+        if increase {
+            self.options.word_sampling = self.options.word_sampling.next();
+        } else {
+            self.options.word_sampling = self.options.word_sampling.previous();
+        }
+    }
+
+    fn change_backspace_policy(&mut self, increase: bool) {
+        if increase {
+            self.options.backspace_policy = self.options.backspace_policy.next();
+        } else {
+            self.options.backspace_policy = self.options.backspace_policy.previous();
+        }
+    }
+
+    fn change_zipf_exponent(&mut self, increase: bool) {
+        let exponent = if increase {
+            self.options.zipf_exponent + ZIPF_EXPONENT_STEP
+        } else {
+            self.options.zipf_exponent - ZIPF_EXPONENT_STEP
+        };
+        // round to the step, so repeated steps don't drift
+        let exponent = (exponent / ZIPF_EXPONENT_STEP).round() * ZIPF_EXPONENT_STEP;
+        self.options.zipf_exponent = exponent.clamp(ZIPF_EXPONENT_MIN, ZIPF_EXPONENT_MAX);
+    }
+
+    // fixing the seed keeps the text of the current test, so it can be shared
+    fn toggle_seed(&mut self) {
+        self.options.seed = match self.options.seed {
+            Some(_) => None,
+            None => Some(self.typing_test.seed),
+        };
+    }
+
+    fn change_code_language(&mut self, increase: bool) {
+        let languages = list_code_languages();
+        let Some(current) = languages.iter().position(|l| *l == self.options.code_language) else {
+            if let Some(first) = languages.first() {
+                self.options.code_language = first.clone();
+            }
+            return;
+        };
+        let index = if increase {
+            (current + 1).min(languages.len() - 1)
+        } else {
+            current.saturating_sub(1)
+        };
+        self.options.code_language = languages[index].clone();
+    }
+
+    /// Save pasted text as custom text and select it for the next test.
+    fn use_pasted_text(&mut self, text: &str) {
+        match save_pasted_text(text) {
+            Ok(path) => {
+                info!("Pasted text saved to {}", path.display());
+                self.options.custom_text_path = Some(path);
+                self.options.test_type = TestType::Custom;
+                self.reset_test = true;
+                self.refresh_zipf_support();
+                self.save_options();
+            }
+            Err(e) => error!("Could not save pasted text: {}", e),
+        }
+    }
+
     fn change_ui_language(&mut self, increase: bool) {
         // Implementierung für das Ändern des Testtyps
         if increase {
@@ -220,14 +610,20 @@ impl App {
 
     fn handle_test_reset(&mut self) {
         if self.reset_test {
-            self.typing_test
-                .reset(self.options.test_language, self.options.test_type);
+            self.typing_test.reset(
+                &self.options,
+                &self.languages.resolve(&self.options.test_language),
+                &self.key_stats,
+            );
+            self.wrapped = WrappedLines::new(self.options.test_type == TestType::Code);
+            self.refresh_personal_best();
             self.reset_test = false;
         }
     }
 
     pub fn run(&mut self, terminal: &mut Terminal<impl Backend>) -> io::Result<()> {
         let mut last_update = Instant::now();
+        let mut last_tick = Instant::now();
         let update_interval = Duration::from_secs(1);
 
         loop {
@@ -246,55 +642,72 @@ impl App {
                     f,
                     &self.state,
                     &self.selected_tab,
-                    &self.typing_test,
+                    TypingView {
+                        typing_test: &self.typing_test,
+                        wrapped: &mut self.wrapped,
+                    },
                     &self.options,
                     &self.options_state,
+                    ProfileView {
+                        personal_best: self.personal_best,
+                        result_history: &self.result_history,
+                        tests_started: self.tests_started,
+                        key_stats: &self.key_stats,
+                        account_table_state: &mut self.account_table_state,
+                    },
                 )
             })?;
 
+            if let AppState::StartScreen | AppState::RunningTest = self.state {
+                self.handle_test_reset();
+            }
+
+            // Wait for input until the next tick is due
+            let timeout = TICK_RATE.saturating_sub(last_tick.elapsed());
+            self.handle_key_event(timeout)?;
+            if last_tick.elapsed() >= TICK_RATE {
+                last_tick = Instant::now();
+            }
+
             // State Maschine: Main program logic
             match self.state {
                 AppState::StartScreen => {
-                    self.handle_test_reset();
-                    self.handle_key_event()?;
-                    if self.typing_test.progress() > 0 {
+                    // timer starts with the first typed char
+                    if self.typing_test.start_time.is_some() {
                         self.typing_test.update_test_data();
                         last_update = Instant::now();
                         self.state = AppState::RunningTest;
+                        self.count_started_test();
                     }
                 }
                 AppState::RunningTest => {
-                    self.handle_test_reset();
-                    self.handle_key_event()?;
-
                     // if time race enabled
-                    if self.options.time_race_enabled {
+                    if self.options.is_time_race() {
                         //check if time Limit is reached
-                        //TODO change hardcoded 30 to constant that is stored in the options
-                        if self.typing_test.get_elapsed_time().as_secs_f64() >= 30.0 {
-                            //end test
-                            self.typing_test.stop_timer();
-                            self.state = AppState::EndScreen;
+                        let time_limit = self.options.time_limit_secs() as f64;
+                        if self.typing_test.get_elapsed_time().as_secs_f64() >= time_limit {
+                            //end test, running out of time is the regular end of a time race
+                            self.finish_test(true);
                         }
                     }
 
-                    // If end of text is reached stop the timer set typing test to finished
+                    // If the last word is committed stop the timer set typing test to finished
                     // and transition to Endscreen
-                    if self.typing_test.index == self.typing_test.target_text.len() {
-                        self.typing_test.text_finished = true;
-                        self.typing_test.stop_timer();
-                        self.state = AppState::EndScreen;
+                    if let AppState::RunningTest = self.state {
+                        if self.typing_test.is_text_complete() {
+                            self.finish_test(true);
+                        }
                     }
                 }
-                AppState::EndScreen => {
-                    self.handle_key_event()?;
-                }
+                AppState::EndScreen => {}
             }
         }
     }
 }
 
 fn cleanup_terminal() -> Result<(), Box<dyn std::error::Error>> {
+    stdout().execute(DisableBracketedPaste)?;
+
     // deactivate  Raw mode
     disable_raw_mode()?;
 
@@ -1,13 +1,44 @@
-use std::default;
-
+use log::{error, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fs, path::PathBuf};
 use strum::{Display, EnumIter, FromRepr};
 
-/*
-    !!!
-    TODO: Check how we can reduce the repeated writing of the next/previous functions for iterating over enums
-*/
+use crate::languages::migrate_language_name;
+
+const APP_DIR: &str = "monkeytype-oxide"; // folder in the platform config and data directories
+const CONFIG_FILE: &str = "config.toml";
+
+// `previous()` and `next()` for stepping through an option in the Options tab,
+// both stay on the first/last variant instead of wrapping around
+macro_rules! impl_step {
+    ($($option:ty),+ $(,)?) => {$(
+        impl $option {
+            pub fn previous(self) -> Self {
+                Self::from_repr((self as usize).saturating_sub(1)).unwrap_or(self)
+            }
+
+            pub fn next(self) -> Self {
+                Self::from_repr((self as usize).saturating_add(1)).unwrap_or(self)
+            }
+        }
+    )+};
+}
+
+impl_step!(
+    Language,
+    TestType,
+    QuoteLength,
+    PunctuationFrequency,
+    WordSampling,
+    CustomTextMode,
+    BackspacePolicy,
+    TestMode,
+    WordCount,
+    TimeRaceDuration,
+);
 
-#[derive(Default, Clone, Copy, Display, FromRepr, EnumIter)]
+// Language of the interface, the test languages are discovered from the word lists
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
 pub enum Language {
     #[default]
     #[strum(to_string = "English")]
@@ -15,22 +46,8 @@ pub enum Language {
     #[strum(to_string = "German")]
     De,
 }
-impl Language{
-    pub fn previous(self) -> Self {
-        let current_index: usize = self as usize;
-        let previous_index = current_index.saturating_sub(1);
-        Self::from_repr(previous_index).unwrap_or(self)
-    }
-
-    /// Get the next tab, if there is no next tab return the current tab.
-    pub fn next(self) -> Self {
-        let current_index = self as usize;
-        let next_index = current_index.saturating_add(1);
-        Self::from_repr(next_index).unwrap_or(self)
-    }
-}
 
-#[derive(Default, Clone, Copy, Display, FromRepr, EnumIter)]
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
 pub enum TestType {
     #[strum(to_string = "Random Words (Chaos)")]
     RandomWords, // full on random words, there willl be stuff you never heard about
@@ -43,38 +60,437 @@ pub enum TestType {
     Quotes, // random qutos
     #[strum(to_string = "Jokes")]
     Jokes, // silly jokes
+    #[strum(to_string = "Practice Weaknesses")]
+    Weaknesses, // words with the chars and bigrams the user misses most
+    #[strum(to_string = "Code")]
+    Code, // source code snippets, typed with line breaks and indentation
+    #[strum(to_string = "Custom Text")]
+    Custom, // text from a file of the user
 }
 impl TestType{
-    pub fn previous(self) -> Self {
-        let current_index: usize = self as usize;
-        let previous_index = current_index.saturating_sub(1);
-        Self::from_repr(previous_index).unwrap_or(self)
+    /// Word based tests can always generate more text, quotes and jokes have a fixed end.
+    pub fn is_word_based(self) -> bool {
+        matches!(
+            self,
+            TestType::RandomWords
+                | TestType::RandomWords1K
+                | TestType::RandomWords10K
+                | TestType::Weaknesses
+        )
     }
+}
 
-    /// Get the next tab, if there is no next tab return the current tab.
-    pub fn next(self) -> Self {
-        let current_index = self as usize;
-        let next_index = current_index.saturating_add(1);
-        Self::from_repr(next_index).unwrap_or(self)
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
+pub enum QuoteLength {
+    #[default]
+    #[strum(to_string = "All")]
+    All,
+    #[strum(to_string = "Short")]
+    Short, // group 0 of the quote file
+    #[strum(to_string = "Medium")]
+    Medium, // group 1
+    #[strum(to_string = "Long")]
+    Long, // group 2
+    #[strum(to_string = "Thicc")]
+    Thicc, // group 3, the really long ones
+}
+impl QuoteLength {
+    /// Index into the `groups` of a quote file, `None` means every group is allowed.
+    pub fn group_index(self) -> Option<usize> {
+        match self {
+            QuoteLength::All => None,
+            QuoteLength::Short => Some(0),
+            QuoteLength::Medium => Some(1),
+            QuoteLength::Long => Some(2),
+            QuoteLength::Thicc => Some(3),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
+pub enum PunctuationFrequency {
+    #[default]
+    #[strum(to_string = "Off")]
+    Off,
+    #[strum(to_string = "Low")]
+    Low,
+    #[strum(to_string = "Medium")]
+    Medium,
+    #[strum(to_string = "High")]
+    High,
+}
+impl PunctuationFrequency {
+    /// Chance that a word gets a punctuation mark.
+    pub fn probability(self) -> f64 {
+        match self {
+            PunctuationFrequency::Off => 0.0,
+            PunctuationFrequency::Low => 0.1,
+            PunctuationFrequency::Medium => 0.2,
+            PunctuationFrequency::High => 0.35,
+        }
     }
 }
 
-#[derive(Clone)]
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
+pub enum WordSampling {
+    #[default]
+    #[strum(to_string = "Uniform")]
+    Uniform, // every word equally likely, no repeats
+    #[strum(to_string = "Zipf")]
+    Zipf, // weighted by frequency rank, repeats possible
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
+pub enum CustomTextMode {
+    #[default]
+    #[strum(to_string = "Whole Text")]
+    Whole, // type the file from start to end
+    #[strum(to_string = "Random Window")]
+    Window, // word count consecutive words from a random position
+    #[strum(to_string = "Shuffled Words")]
+    Shuffled, // random words of the file
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
+pub enum BackspacePolicy {
+    #[default]
+    #[strum(to_string = "Free")]
+    Free, // go back as far as you like, finished words included
+    #[strum(to_string = "Within Word")]
+    WithinWord, // only the word the cursor is in can be corrected
+    #[strum(to_string = "Disabled")]
+    Disabled, // confidence mode, every keystroke is final
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
+pub enum TestMode {
+    #[default]
+    #[strum(to_string = "Words")]
+    Words, // fixed amount of words, ends with the last word
+    #[strum(to_string = "Time")]
+    Time, // race against the clock, words keep coming
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
+pub enum WordCount {
+    #[strum(to_string = "10")]
+    Ten,
+    #[default]
+    #[strum(to_string = "25")]
+    TwentyFive,
+    #[strum(to_string = "50")]
+    Fifty,
+    #[strum(to_string = "100")]
+    Hundred,
+}
+impl WordCount {
+    pub fn count(self) -> usize {
+        match self {
+            WordCount::Ten => 10,
+            WordCount::TwentyFive => 25,
+            WordCount::Fifty => 50,
+            WordCount::Hundred => 100,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Display, FromRepr, EnumIter, Serialize, Deserialize)]
+pub enum TimeRaceDuration {
+    #[strum(to_string = "15s")]
+    Fifteen,
+    #[default]
+    #[strum(to_string = "30s")]
+    Thirty,
+    #[strum(to_string = "60s")]
+    Sixty,
+    #[strum(to_string = "120s")]
+    OneTwenty,
+    #[strum(to_string = "Custom")]
+    Custom, // uses AppOptions::custom_time_secs
+}
+impl TimeRaceDuration {
+    /// Length of the preset in seconds, `None` for the custom duration.
+    pub fn secs(self) -> Option<u64> {
+        match self {
+            TimeRaceDuration::Fifteen => Some(15),
+            TimeRaceDuration::Thirty => Some(30),
+            TimeRaceDuration::Sixty => Some(60),
+            TimeRaceDuration::OneTwenty => Some(120),
+            TimeRaceDuration::Custom => None,
+        }
+    }
+}
+
+pub const CUSTOM_TIME_STEP: u64 = 5;
+pub const CUSTOM_TIME_MIN: u64 = 5;
+pub const CUSTOM_TIME_MAX: u64 = 600;
+
+pub const ZIPF_EXPONENT_STEP: f64 = 0.1;
+pub const ZIPF_EXPONENT_MIN: f64 = 0.1;
+pub const ZIPF_EXPONENT_MAX: f64 = 3.0;
+
+pub const NUMBERS_PERCENT_STEP: u32 = 5;
+pub const NUMBERS_PERCENT_MAX: u32 = 100;
+
+#[derive(Clone, Serialize)]
 pub struct AppOptions {
     pub ui_language: Language,
-    pub test_language: Language,
+    pub test_language: String, // name of a discovered word list, e.g. "german"
     pub test_type: TestType,
-    pub time_race_enabled: bool,
+    pub quote_length: QuoteLength,
+    pub test_mode: TestMode,
+    pub word_count: WordCount,
+    pub time_race_duration: TimeRaceDuration,
+    pub custom_time_secs: u64,
     pub hardcore_enabled: bool,
+    pub backspace_policy: BackspacePolicy,
+    pub ctrl_h_deletes_word: bool, // ^H is Ctrl+Backspace in most terminals, but plain Backspace in some
+    pub lazy_mode: Vec<String>, // languages typed without diacritics, e.g. "ae" for "ä"
+    pub lenient_accents: bool, // base letter counts for an accented one, reported as accent slip
+    pub custom_text_path: Option<PathBuf>,
+    pub custom_text_mode: CustomTextMode,
+    pub custom_lowercase: bool,
+    pub custom_strip_punctuation: bool,
+    pub punctuation: PunctuationFrequency, // capitals and punctuation marks in random word tests
+    pub numbers_percent: u32, // share of random words replaced by numbers, dates and phone numbers
+    pub word_sampling: WordSampling,
+    pub zipf_exponent: f64, // higher values favour the most frequent words more
+    pub code_language: String, // name of a snippet file in util/code, e.g. "rust"
+    pub code_auto_indent: bool, // indentation after a typed line break is filled in
+    pub seed: Option<u32>, // fixed seed for the text generation, a new random one per test if unset
 }
 impl AppOptions {
     pub fn new() -> Self {
         Self {
-            test_language: Language::En,
+            test_language: String::from("english"),
             test_type: TestType::RandomWords1K,
-            time_race_enabled: false,
+            quote_length: QuoteLength::All,
+            test_mode: TestMode::Words,
+            word_count: WordCount::TwentyFive,
+            time_race_duration: TimeRaceDuration::Thirty,
+            custom_time_secs: 45,
             hardcore_enabled: false,
+            backspace_policy: BackspacePolicy::Free,
+            ctrl_h_deletes_word: true,
+            lazy_mode: vec![String::from("german")],
+            lenient_accents: false,
+            custom_text_path: None,
+            custom_text_mode: CustomTextMode::Whole,
+            custom_lowercase: false,
+            custom_strip_punctuation: false,
+            punctuation: PunctuationFrequency::Off,
+            numbers_percent: 0,
+            word_sampling: WordSampling::Uniform,
+            zipf_exponent: 1.0,
+            code_language: String::from("rust"),
+            code_auto_indent: false,
+            seed: None,
             ui_language: Language::En,
         }
     }
+
+    pub fn is_lazy(&self, language: &str) -> bool {
+        self.lazy_mode.iter().any(|l| l == language)
+    }
+
+    /// Switch between lazy mode and native diacritics for the test language.
+    pub fn toggle_lazy_mode(&mut self) {
+        let language = self.test_language.clone();
+        if self.is_lazy(&language) {
+            self.lazy_mode.retain(|l| *l != language);
+        } else {
+            self.lazy_mode.push(language);
+        }
+    }
+
+    /// True if the test is made of picked words, so word count applies and more words can follow.
+    pub fn is_word_based(&self) -> bool {
+        match self.test_type {
+            TestType::Custom => self.custom_text_mode != CustomTextMode::Whole,
+            test_type => test_type.is_word_based(),
+        }
+    }
+
+    /// True if the generated words get capitals and punctuation marks.
+    pub fn has_punctuation(&self) -> bool {
+        self.punctuation != PunctuationFrequency::Off && self.test_type.is_word_based()
+    }
+
+    /// Zipf exponent if words are weighted by frequency, None for uniform sampling.
+    pub fn zipf_exponent(&self) -> Option<f64> {
+        match self.word_sampling {
+            WordSampling::Uniform => None,
+            WordSampling::Zipf => Some(self.zipf_exponent),
+        }
+    }
+
+    /// Language a result is filed under, the programming language for code tests.
+    pub fn result_language(&self) -> &str {
+        match self.test_type {
+            TestType::Code => &self.code_language,
+            _ => &self.test_language,
+        }
+    }
+
+    /// True if some generated words are replaced by numbers.
+    pub fn has_numbers(&self) -> bool {
+        self.numbers_percent > 0 && self.test_type.is_word_based()
+    }
+
+    pub fn is_time_race(&self) -> bool {
+        self.test_mode == TestMode::Time
+    }
+
+    /// The time limit of a time race in seconds.
+    pub fn time_limit_secs(&self) -> u64 {
+        self.time_race_duration
+            .secs()
+            .unwrap_or(self.custom_time_secs)
+    }
+}
+
+impl Default for AppOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Directory for results, stats, texts and languages of the user, `~/.local/share/monkeytype-oxide` on linux.
+/// `None` when the platform has no data directory, nothing is saved then.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR))
+}
+
+impl AppOptions {
+    /// Path of the config file, `~/.config/monkeytype-oxide/config.toml` on linux.
+    pub fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(APP_DIR).join(CONFIG_FILE))
+    }
+
+    /// Load the options from the config file, everything missing or invalid falls back to the defaults.
+    pub fn load() -> Self {
+        let Some(path) = Self::config_path() else {
+            warn!("No config directory found, using default options");
+            return Self::default();
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                warn!("Could not read config {}: {}, using default options", path.display(), e);
+                return Self::default();
+            }
+        };
+
+        match content.parse::<toml::Table>() {
+            Ok(table) => Self::from_table(table),
+            Err(e) => {
+                warn!("Invalid config {}: {}, using default options", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    fn from_table(table: toml::Table) -> Self {
+        let mut options = Self::default();
+        // older configs listed the languages that keep their diacritics instead
+        let mut keep_diacritics: Vec<String> = Vec::new();
+
+        for (key, value) in table {
+            match key.as_str() {
+                "ui_language" => read_value(&key, value, &mut options.ui_language),
+                "test_language" => read_value(&key, value, &mut options.test_language),
+                "test_type" => read_value(&key, value, &mut options.test_type),
+                "quote_length" => read_value(&key, value, &mut options.quote_length),
+                "test_mode" => read_value(&key, value, &mut options.test_mode),
+                "word_count" => read_value(&key, value, &mut options.word_count),
+                "time_race_duration" => read_value(&key, value, &mut options.time_race_duration),
+                "custom_time_secs" => read_value(&key, value, &mut options.custom_time_secs),
+                "hardcore_enabled" => read_value(&key, value, &mut options.hardcore_enabled),
+                "backspace_policy" => read_value(&key, value, &mut options.backspace_policy),
+                "ctrl_h_deletes_word" => read_value(&key, value, &mut options.ctrl_h_deletes_word),
+                "lazy_mode" => read_value(&key, value, &mut options.lazy_mode),
+                "keep_diacritics" => read_value(&key, value, &mut keep_diacritics),
+                "lenient_accents" => read_value(&key, value, &mut options.lenient_accents),
+                "custom_text_path" => read_value(&key, value, &mut options.custom_text_path),
+                "custom_text_mode" => read_value(&key, value, &mut options.custom_text_mode),
+                "custom_lowercase" => read_value(&key, value, &mut options.custom_lowercase),
+                "custom_strip_punctuation" => {
+                    read_value(&key, value, &mut options.custom_strip_punctuation)
+                }
+                "punctuation" => read_value(&key, value, &mut options.punctuation),
+                "numbers_percent" => read_value(&key, value, &mut options.numbers_percent),
+                "word_sampling" => read_value(&key, value, &mut options.word_sampling),
+                "zipf_exponent" => read_value(&key, value, &mut options.zipf_exponent),
+                "code_language" => read_value(&key, value, &mut options.code_language),
+                "code_auto_indent" => read_value(&key, value, &mut options.code_auto_indent),
+                "seed" => read_value(&key, value, &mut options.seed),
+                _ => warn!("Unknown config option '{}', ignoring it", key),
+            }
+        }
+        options.test_language = migrate_language_name(options.test_language);
+        let keep_diacritics: Vec<String> = keep_diacritics.into_iter().map(migrate_language_name).collect();
+        options.lazy_mode.retain(|language| !keep_diacritics.contains(language));
+
+        if !(CUSTOM_TIME_MIN..=CUSTOM_TIME_MAX).contains(&options.custom_time_secs) {
+            warn!(
+                "Custom duration {}s out of range, using {}s",
+                options.custom_time_secs,
+                Self::default().custom_time_secs
+            );
+            options.custom_time_secs = Self::default().custom_time_secs;
+        }
+
+        if !(ZIPF_EXPONENT_MIN..=ZIPF_EXPONENT_MAX).contains(&options.zipf_exponent) {
+            warn!(
+                "Zipf exponent {} out of range, using {}",
+                options.zipf_exponent,
+                Self::default().zipf_exponent
+            );
+            options.zipf_exponent = Self::default().zipf_exponent;
+        }
+
+        if options.numbers_percent > NUMBERS_PERCENT_MAX {
+            warn!("Numbers share {}% out of range, using {}%", options.numbers_percent, NUMBERS_PERCENT_MAX);
+            options.numbers_percent = NUMBERS_PERCENT_MAX;
+        }
+
+        options
+    }
+
+    /// Write the options to the config file, errors are only logged.
+    pub fn save(&self) {
+        let Some(path) = Self::config_path() else {
+            warn!("No config directory found, options are not saved");
+            return;
+        };
+
+        let content = match toml::to_string_pretty(self) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Could not serialize options: {}", e);
+                return;
+            }
+        };
+
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                error!("Could not create config directory {}: {}", parent.display(), e);
+                return;
+            }
+        }
+
+        if let Err(e) = fs::write(&path, content) {
+            error!("Could not write config {}: {}", path.display(), e);
+        }
+    }
+}
+
+// Overwrite `target` with the parsed value, keep the default if the value is invalid
+fn read_value<T: DeserializeOwned>(key: &str, value: toml::Value, target: &mut T) {
+    match value.try_into() {
+        Ok(parsed) => *target = parsed,
+        Err(e) => warn!("Invalid value for config option '{}': {}, using default", key, e),
+    }
 }
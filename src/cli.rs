@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+
+use crate::app_options::{AppOptions, TestType};
+
+/// Help text, printed before the terminal switches to the alternate screen.
+pub const USAGE: &str = "Usage: monkeytype-oxide [--custom-text <file>] [--seed <number>]
+
+Options:
+  --custom-text <file>  Type the given text or markdown file
+  --seed <number>       Generate the same text as everyone else using this seed
+  -h, --help            Print this help";
+
+/// Settings from the command line, they win over the config file for this run.
+#[derive(Default, Clone)]
+pub struct CliArgs {
+    pub custom_text: Option<PathBuf>,
+    pub seed: Option<u32>,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Help,
+    Invalid(String),
+}
+
+impl CliArgs {
+    pub fn parse() -> Result<Self, CliError> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    /// Put the command line settings over the ones from the config.
+    pub fn apply(&self, options: &mut AppOptions) {
+        if let Some(path) = &self.custom_text {
+            options.test_type = TestType::Custom;
+            options.custom_text_path = Some(path.clone());
+        }
+        if let Some(seed) = self.seed {
+            options.seed = Some(seed);
+        }
+    }
+
+    /// Undo `apply` before saving, overrides that were changed since in the options tab are kept.
+    pub fn revert(&self, options: &mut AppOptions, config: &AppOptions) {
+        if let Some(path) = &self.custom_text {
+            if options.test_type == TestType::Custom && options.custom_text_path.as_ref() == Some(path) {
+                options.test_type = config.test_type;
+                options.custom_text_path = config.custom_text_path.clone();
+            }
+        }
+        if self.seed.is_some() && options.seed == self.seed {
+            options.seed = config.seed;
+        }
+    }
+
+    fn parse_from(mut args: impl Iterator<Item = String>) -> Result<Self, CliError> {
+        let mut cli = Self::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Err(CliError::Help),
+                "--custom-text" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| CliError::Invalid(String::from("--custom-text needs a file")))?;
+                    let path = PathBuf::from(path);
+                    if !path.is_file() {
+                        return Err(CliError::Invalid(format!("{} is not a file", path.display())));
+                    }
+                    cli.custom_text = Some(path);
+                }
+                "--seed" => {
+                    let seed = args
+                        .next()
+                        .ok_or_else(|| CliError::Invalid(String::from("--seed needs a number")))?;
+                    let seed = seed
+                        .parse()
+                        .map_err(|_| CliError::Invalid(format!("{} is not a valid seed", seed)))?;
+                    cli.seed = Some(seed);
+                }
+                other => return Err(CliError::Invalid(format!("Unknown argument {}", other))),
+            }
+        }
+        Ok(cli)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, CliError> {
+        CliArgs::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_seed() {
+        let cli = parse(&["--seed", "42"]).unwrap();
+        assert_eq!(cli.seed, Some(42));
+        assert!(cli.custom_text.is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_seed() {
+        assert!(matches!(parse(&["--seed", "abc"]), Err(CliError::Invalid(_))));
+        assert!(matches!(parse(&["--seed", "-1"]), Err(CliError::Invalid(_))));
+        assert!(matches!(parse(&["--seed"]), Err(CliError::Invalid(_))));
+    }
+
+    #[test]
+    fn seed_override_is_not_saved() {
+        let config = AppOptions::default();
+        let cli = parse(&["--seed", "7"]).unwrap();
+        let mut options = config.clone();
+        cli.apply(&mut options);
+        assert_eq!(options.seed, Some(7));
+
+        cli.revert(&mut options, &config);
+        assert_eq!(options.seed, config.seed);
+    }
+}
@@ -0,0 +1,208 @@
+use log::warn;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app_options::{data_dir, AppOptions};
+
+const TEXTS_DIR: &str = "texts";
+const PASTED_FILE: &str = "pasted.txt";
+
+/// Directory for the user's own texts, `~/.local/share/monkeytype-oxide/texts` on linux.
+pub fn custom_text_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(TEXTS_DIR))
+}
+
+/// All text and markdown files in the custom text directory, sorted by name.
+pub fn list_custom_texts() -> Vec<PathBuf> {
+    let Some(dir) = custom_text_dir() else {
+        return Vec::new();
+    };
+
+    let mut texts: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| is_text_file(path))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    texts.sort();
+    texts
+}
+
+fn is_text_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("txt") | Some("md") | Some("markdown")
+    )
+}
+
+/// Store pasted text in the custom text directory, so it survives a restart like a normal file.
+pub fn save_pasted_text(text: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = custom_text_dir().ok_or("No data directory found")?;
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(PASTED_FILE);
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+/// Read the file and turn it into a single line of typeable text.
+pub fn load_custom_text(path: &Path, options: &AppOptions) -> Result<String, Box<dyn Error>> {
+    let raw = fs::read_to_string(path)?;
+
+    let is_markdown = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("md") | Some("markdown")
+    );
+    let text = if is_markdown { strip_markdown(&raw) } else { raw };
+
+    let text = normalize_custom_text(&text, options.custom_lowercase, options.custom_strip_punctuation);
+    if text.is_empty() {
+        warn!("Custom text {} is empty", path.display());
+        return Err(format!("{} contains no text", path.display()).into());
+    }
+    Ok(text)
+}
+
+/// Collapse whitespace and apply the optional lowercase and punctuation filters.
+pub fn normalize_custom_text(text: &str, lowercase: bool, strip_punctuation: bool) -> String {
+    let text = if lowercase { text.to_lowercase() } else { text.to_string() };
+
+    let text: String = if strip_punctuation {
+        text.chars()
+            .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+            .collect()
+    } else {
+        text
+    };
+
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// Drop the markup that nobody wants to type: headings, quotes, list bullets, code fences, emphasis and link targets.
+// Underscores are left alone, in typed texts they are names like snake_case or __init__ far more often than emphasis
+fn strip_markdown(text: &str) -> String {
+    let mut lines = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim_start();
+        if line.starts_with("```") || line.starts_with("---") {
+            continue;
+        }
+
+        let line = line.trim_start_matches('#').trim_start_matches('>').trim_start();
+        let line = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .or_else(|| line.strip_prefix("+ "))
+            .unwrap_or(line);
+
+        let line = strip_links(line);
+        let line = strip_delimiter_pairs(&line, "`");
+        let line = strip_delimiter_pairs(&line, "**");
+        lines.push(strip_delimiter_pairs(&line, "*"));
+    }
+
+    lines.join("\n")
+}
+
+// [text](url) -> text
+fn strip_links(line: &str) -> String {
+    let mut result = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find('[') {
+        let Some(middle) = rest[start..].find("](").map(|i| start + i) else {
+            break;
+        };
+        let Some(end) = rest[middle..].find(')').map(|i| middle + i) else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        result.push_str(&rest[start + 1..middle]);
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+// **bold** -> bold, `code` -> code. A delimiter without a partner or with a space on its inner side
+// is a literal char, like in a*b or 2 * 3
+fn strip_delimiter_pairs(line: &str, delimiter: &str) -> String {
+    let mut result = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find(delimiter) {
+        let inner_start = start + delimiter.len();
+        let opens = rest[inner_start..].starts_with(|c: char| !c.is_whitespace());
+        let end = rest[inner_start..].find(delimiter).map(|i| inner_start + i);
+
+        match end {
+            Some(end) if opens && end > inner_start && !rest[..end].ends_with(char::is_whitespace) => {
+                result.push_str(&rest[..start]);
+                result.push_str(&rest[inner_start..end]);
+                rest = &rest[end + delimiter.len()..];
+            }
+            _ => {
+                result.push_str(&rest[..inner_start]);
+                rest = &rest[inner_start..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Random run of `word_count` consecutive words, the whole text if it is shorter.
+pub fn random_window(text: &str, word_count: usize, rng: &mut StdRng) -> String {
+    let words: Vec<&str> = text.split(' ').collect();
+    if words.len() <= word_count {
+        return text.to_string();
+    }
+
+    let start = rng.gen_range(0..=words.len() - word_count);
+    words[start..start + word_count].join(" ")
+}
+
+/// Short name for the options tab.
+pub fn custom_text_label(path: Option<&Path>) -> String {
+    path.and_then(|path| path.file_name())
+        .map_or(String::from("none"), |name| name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_markdown_removes_markup() {
+        let markdown = "# Title\n\n> a **bold** and *em* quote\n- item with `code`\n```\nfenced\n```";
+        assert_eq!(strip_markdown(markdown), "Title\n\na bold and em quote\nitem with code\nfenced");
+    }
+
+    #[test]
+    fn strip_markdown_keeps_literal_delimiters() {
+        assert_eq!(strip_markdown("a*b and 2 * 3"), "a*b and 2 * 3");
+        assert_eq!(strip_markdown("call __init__ on my_var"), "call __init__ on my_var");
+        assert_eq!(strip_markdown("a lone ` backtick"), "a lone ` backtick");
+    }
+
+    #[test]
+    fn strip_links_keeps_the_link_text() {
+        assert_eq!(strip_links("see [the docs](https://example.com) now"), "see the docs now");
+        assert_eq!(strip_links("[a](x) and [b](y)"), "a and b");
+        assert_eq!(strip_links("no [link] here"), "no [link] here");
+    }
+
+    #[test]
+    fn normalize_collapses_whitespace_and_filters() {
+        assert_eq!(normalize_custom_text("  Hello,\n\tWorld!  ", false, false), "Hello, World!");
+        assert_eq!(normalize_custom_text("Hello, World!", true, false), "hello, world!");
+        assert_eq!(normalize_custom_text("Hello, World!", false, true), "Hello World");
+    }
+}
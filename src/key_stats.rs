@@ -0,0 +1,160 @@
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app_options::data_dir;
+
+const KEY_STATS_FILE: &str = "key_stats.json";
+
+// attempts added to every key before the error rate is taken, so a single miss doesn't make a key the worst one
+const RATE_PRIOR: f64 = 10.0;
+
+/// How often a char or bigram was typed and how often it was wrong.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct KeyStat {
+    pub attempts: u64,
+    pub errors: u64,
+}
+
+impl KeyStat {
+    /// Error rate, damped towards zero for keys with few attempts.
+    pub fn error_rate(&self) -> f64 {
+        self.errors as f64 / (self.attempts as f64 + RATE_PRIOR)
+    }
+}
+
+/// Errors per target char and per pair of chars within a word, keys are lowercase.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct KeyStats {
+    pub chars: BTreeMap<String, KeyStat>,
+    pub bigrams: BTreeMap<String, KeyStat>,
+}
+
+impl KeyStats {
+    /// Count one typed target grapheme, `previous` is the target grapheme before it.
+    pub fn record(&mut self, previous: Option<&str>, target: &str, wrong: bool) {
+        let target = target.to_lowercase();
+        if is_separator(&target) {
+            return;
+        }
+
+        add(self.chars.entry(target.clone()).or_default(), wrong);
+
+        if let Some(previous) = previous.map(str::to_lowercase) {
+            if !is_separator(&previous) {
+                add(self.bigrams.entry(previous + &target).or_default(), wrong);
+            }
+        }
+    }
+
+    /// Add the counts of another test.
+    pub fn merge(&mut self, other: &KeyStats) {
+        for (key, stat) in &other.chars {
+            merge_stat(self.chars.entry(key.clone()).or_default(), stat);
+        }
+        for (key, stat) in &other.bigrams {
+            merge_stat(self.bigrams.entry(key.clone()).or_default(), stat);
+        }
+    }
+
+    /// The `count` chars with the highest error rate, only those with errors.
+    pub fn worst_chars(&self, count: usize) -> Vec<(&str, f64)> {
+        worst(&self.chars, count)
+    }
+
+    /// The `count` bigrams with the highest error rate, only those with errors.
+    pub fn worst_bigrams(&self, count: usize) -> Vec<(&str, f64)> {
+        worst(&self.bigrams, count)
+    }
+
+    /// How much practice a word offers: the summed error rates of its chars and bigrams.
+    pub fn word_score(&self, word: &str) -> f64 {
+        let word = word.to_lowercase();
+        let chars: Vec<char> = word.chars().collect();
+
+        let char_score: f64 = chars
+            .iter()
+            .filter_map(|c| self.chars.get(&c.to_string()))
+            .map(KeyStat::error_rate)
+            .sum();
+        let bigram_score: f64 = chars
+            .windows(2)
+            .filter_map(|pair| self.bigrams.get(&pair.iter().collect::<String>()))
+            .map(KeyStat::error_rate)
+            .sum();
+
+        char_score + bigram_score
+    }
+}
+
+fn add(stat: &mut KeyStat, wrong: bool) {
+    stat.attempts += 1;
+    if wrong {
+        stat.errors += 1;
+    }
+}
+
+fn merge_stat(stat: &mut KeyStat, other: &KeyStat) {
+    stat.attempts += other.attempts;
+    stat.errors += other.errors;
+}
+
+fn is_separator(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+fn worst(stats: &BTreeMap<String, KeyStat>, count: usize) -> Vec<(&str, f64)> {
+    let mut rates: Vec<(&str, f64)> = stats
+        .iter()
+        .filter(|(_, stat)| stat.errors > 0)
+        .map(|(key, stat)| (key.as_str(), stat.error_rate()))
+        .collect();
+    rates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    rates.truncate(count);
+    rates
+}
+
+pub struct KeyStatsStore {
+    path: PathBuf,
+}
+
+impl KeyStatsStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// `key_stats.json` in the data directory.
+    pub fn open_default() -> Option<Self> {
+        data_dir().map(|dir| Self::new(dir.join(KEY_STATS_FILE)))
+    }
+
+    /// The stats recorded so far, empty if there are none yet or the file is broken.
+    pub fn load(&self) -> KeyStats {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return KeyStats::default(),
+            Err(e) => {
+                error!("Could not read key stats {}: {}", self.path.display(), e);
+                return KeyStats::default();
+            }
+        };
+
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Invalid key stats {}: {}, starting over", self.path.display(), e);
+            KeyStats::default()
+        })
+    }
+
+    pub fn save(&self, stats: &KeyStats) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(stats)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use log::warn;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::app_options::data_dir;
+use crate::text_gen::{capitalize, WordSetSize, BASE_PATH};
+
+const LANGUAGES_DIR: &str = "languages";
+
+// Only the header of a word list, the words themselves are loaded when a test is generated
+#[derive(Deserialize)]
+struct LanguageHeader {
+    name: String,
+    #[serde(default)]
+    bcp47: Option<String>,
+    #[serde(default, rename = "noLazyMode")]
+    no_lazy_mode: bool,
+    #[serde(default, rename = "orderedByFrequency")]
+    ordered_by_frequency: bool,
+}
+
+/// A language found on disk, made up of the word lists `<name>.json`, `<name>_1k.json` and `<name>_10k.json`.
+#[derive(Clone)]
+pub struct LanguageInfo {
+    pub name: String,
+    pub bcp47: Option<String>,
+    pub dir: PathBuf, // directory of the word lists, quotes and jokes live in its subdirectories
+    pub word_sets: Vec<WordSetSize>,
+    pub ranked_word_sets: Vec<WordSetSize>, // lists ordered by frequency, only these can be Zipf sampled
+    pub no_lazy_mode: bool, // the spelling must not be simplified, e.g. english
+}
+
+impl LanguageInfo {
+    /// Placeholder for a language without word lists, generating a test shows the loading error.
+    fn missing(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            bcp47: None,
+            dir: PathBuf::from(BASE_PATH),
+            word_sets: Vec::new(),
+            ranked_word_sets: Vec::new(),
+            no_lazy_mode: false,
+        }
+    }
+
+    /// Primary language subtag of the bcp47 code, e.g. "de" for "de-DE".
+    pub fn language_code(&self) -> Option<String> {
+        self.bcp47
+            .as_ref()
+            .and_then(|code| code.split('-').next())
+            .map(|code| code.to_lowercase())
+    }
+
+    pub fn has_word_set(&self, size: WordSetSize) -> bool {
+        self.word_sets.contains(&size)
+    }
+
+    pub fn is_ranked(&self, size: WordSetSize) -> bool {
+        self.ranked_word_sets.contains(&size)
+    }
+}
+
+/// Word list name for a language saved by older versions, which stored "En" and "De" instead.
+pub fn migrate_language_name(name: String) -> String {
+    match name.as_str() {
+        "En" => String::from("english"),
+        "De" => String::from("german"),
+        _ => name,
+    }
+}
+
+/// "german" -> "German"
+pub fn display_name(name: &str) -> String {
+    capitalize(name)
+}
+
+/// Every language found in the bundled `util` directory and the user's data directory.
+pub struct LanguageRegistry {
+    languages: Vec<LanguageInfo>,
+}
+
+impl LanguageRegistry {
+    pub fn discover() -> Self {
+        let mut dirs = vec![PathBuf::from(BASE_PATH)];
+        if let Some(data_dir) = data_dir() {
+            dirs.push(data_dir.join(LANGUAGES_DIR));
+        }
+        Self::discover_in(&dirs)
+    }
+
+    pub fn discover_in(dirs: &[PathBuf]) -> Self {
+        let mut registry = Self {
+            languages: Vec::new(),
+        };
+
+        for dir in dirs {
+            registry.scan_dir(dir);
+        }
+
+        if registry.languages.is_empty() {
+            warn!("No word lists found in {:?}", dirs);
+        }
+        registry.languages.sort_by(|a, b| a.name.cmp(&b.name));
+        registry
+    }
+
+    fn scan_dir(&mut self, dir: &Path) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            // the user directory is optional
+            Err(_) => return,
+        };
+
+        // languages of earlier directories win, a second directory can only add new ones
+        let known: Vec<String> = self.languages.iter().map(|l| l.name.clone()).collect();
+
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let header = match read_header(&path) {
+                Ok(header) => header,
+                Err(e) => {
+                    warn!("Skipping {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            let (name, size) = split_word_set_name(&header.name);
+            if known.contains(&name) {
+                warn!("{} is already provided by another directory, skipping {}", name, path.display());
+                continue;
+            }
+
+            let ranked_word_sets = if header.ordered_by_frequency { vec![size] } else { Vec::new() };
+            match self.languages.iter_mut().find(|l| l.name == name) {
+                Some(language) => {
+                    language.word_sets.push(size);
+                    language.ranked_word_sets.extend(ranked_word_sets);
+                    language.no_lazy_mode |= header.no_lazy_mode;
+                    if language.bcp47.is_none() {
+                        language.bcp47 = header.bcp47;
+                    }
+                }
+                None => self.languages.push(LanguageInfo {
+                    name,
+                    bcp47: header.bcp47,
+                    dir: dir.to_path_buf(),
+                    word_sets: vec![size],
+                    ranked_word_sets,
+                    no_lazy_mode: header.no_lazy_mode,
+                }),
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LanguageInfo> {
+        self.languages.iter().find(|l| l.name == name)
+    }
+
+    /// The language with that name, or a placeholder if it is not installed.
+    pub fn resolve(&self, name: &str) -> LanguageInfo {
+        self.get(name)
+            .cloned()
+            .unwrap_or_else(|| LanguageInfo::missing(name))
+    }
+
+    pub fn first(&self) -> Option<&LanguageInfo> {
+        self.languages.first()
+    }
+
+    /// Name of the language after `name`, stays on the last one.
+    pub fn next(&self, name: &str) -> String {
+        match self.position(name) {
+            Some(index) => self.languages[(index + 1).min(self.languages.len() - 1)].name.clone(),
+            None => self.first().map_or(name.to_string(), |l| l.name.clone()),
+        }
+    }
+
+    /// Name of the language before `name`, stays on the first one.
+    pub fn previous(&self, name: &str) -> String {
+        match self.position(name) {
+            Some(index) => self.languages[index.saturating_sub(1)].name.clone(),
+            None => self.first().map_or(name.to_string(), |l| l.name.clone()),
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.languages.iter().position(|l| l.name == name)
+    }
+}
+
+fn read_header(path: &Path) -> Result<LanguageHeader, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let header: LanguageHeader = serde_json::from_reader(reader)?;
+    Ok(header)
+}
+
+// "german_10k" -> ("german", TenK)
+fn split_word_set_name(name: &str) -> (String, WordSetSize) {
+    for size in [WordSetSize::OneK, WordSetSize::TenK] {
+        if let Some(base) = name.strip_suffix(size.to_suffix()) {
+            return (base.to_string(), size);
+        }
+    }
+    (name.to_string(), WordSetSize::Base)
+}
@@ -1,24 +1,51 @@
 mod app;
+mod cli;
 mod ui;
 mod type_test;
 mod app_options;
+mod custom_text;
 mod text_gen;
+mod languages;
+mod key_stats;
+mod results;
 
 use ratatui::prelude::*;
+use std::fs::{self, File};
 use std::io;
 
 use crate::app::App;
+use crate::app_options::data_dir;
+use crate::cli::{CliArgs, CliError, USAGE};
+
+const LOG_FILE: &str = "app.log";
 
 fn main() -> io::Result<()> {
-    
+    let args = match CliArgs::parse() {
+        Ok(args) => args,
+        Err(CliError::Help) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(CliError::Invalid(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    init_logger();
+
     crossterm::terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
-    crossterm::execute!(stdout, crossterm::terminal::EnterAlternateScreen)?;
+    crossterm::execute!(
+        stdout,
+        crossterm::terminal::EnterAlternateScreen,
+        crossterm::event::EnableBracketedPaste
+    )?;
 
     let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
     terminal.clear()?;
 
-    let mut app = App::new();
+    let mut app = App::new(args);
 
     let result = app.run(&mut terminal);
 
@@ -26,7 +53,26 @@ fn main() -> io::Result<()> {
     terminal.show_cursor()?;
 
     crossterm::terminal::disable_raw_mode()?;
-    crossterm::execute!(io::stdout(), crossterm::terminal::LeaveAlternateScreen)?;
+    crossterm::execute!(
+        io::stdout(),
+        crossterm::event::DisableBracketedPaste,
+        crossterm::terminal::LeaveAlternateScreen
+    )?;
 
     result
-}
\ No newline at end of file
+}
+
+// The terminal belongs to the UI, so warnings go to `app.log` in the data directory.
+// The file starts empty with every run, RUST_LOG overrides the level
+fn init_logger() {
+    let Some(dir) = data_dir() else {
+        return;
+    };
+    let file = fs::create_dir_all(&dir).and_then(|_| File::create(dir.join(LOG_FILE)));
+    match file {
+        Ok(file) => env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+            .target(env_logger::Target::Pipe(Box::new(file)))
+            .init(),
+        Err(e) => eprintln!("Could not open the log file: {}", e),
+    }
+}
@@ -0,0 +1,331 @@
+use chrono::{DateTime, Local};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::app_options::{data_dir, AppOptions, QuoteLength, TestMode, TestType};
+use crate::languages::{display_name, migrate_language_name};
+use crate::type_test::{TestDataPerSecond, TypingTest};
+
+const RESULTS_FILE: &str = "results.jsonl";
+const TESTS_STARTED_FILE: &str = "tests_started.txt";
+
+/// One finished (or failed) test as it is stored in the results file.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TestResult {
+    pub timestamp: DateTime<Local>,
+    pub language: String,
+    pub test_type: TestType,
+    pub test_mode: TestMode,
+    pub quote_length: Option<QuoteLength>, // only for quotes
+    pub time_limit_secs: Option<u64>,      // only for time races
+    pub word_count: Option<usize>,         // only for word tests
+    pub hardcore: bool,
+    #[serde(default)]
+    pub punctuation: bool, // capitals and punctuation marks in random words
+    #[serde(default)]
+    pub numbers: bool, // numbers mixed into random words
+    pub completed: bool,
+    pub wpm: i64,
+    pub wpm_raw: i64,
+    pub accuracy: f64,
+    pub mistakes: usize,
+    pub duration_secs: f64,
+    pub test_data_history: Vec<TestDataPerSecond>,
+}
+
+impl TestResult {
+    pub fn from_test(typing_test: &TypingTest) -> Self {
+        let options = typing_test.options();
+        let key = PbKey::from_options(options);
+
+        Self {
+            timestamp: Local::now(),
+            language: options.result_language().to_string(),
+            test_type: options.test_type,
+            test_mode: options.test_mode,
+            quote_length: matches!(options.test_type, TestType::Quotes)
+                .then_some(options.quote_length),
+            time_limit_secs: key.time_limit_secs,
+            word_count: key.word_count,
+            hardcore: key.hardcore,
+            punctuation: key.punctuation,
+            numbers: key.numbers,
+            completed: typing_test.text_finished,
+            wpm: typing_test.get_wpm(),
+            wpm_raw: typing_test.get_wpm_raw(),
+            accuracy: typing_test.accuracy(),
+            mistakes: typing_test.mistakes,
+            duration_secs: typing_test.get_elapsed_time().as_secs_f64(),
+            test_data_history: typing_test.test_data_history.clone(),
+        }
+    }
+}
+
+impl TestResult {
+    /// Everything results are grouped by, e.g. "English, Quotes, time 30s".
+    pub fn mode_label(&self) -> String {
+        format!(
+            "{}, {}, {}",
+            display_name(&self.language),
+            self.test_type,
+            self.settings_label()
+        )
+    }
+
+    /// Length and modifiers of the test, e.g. "time 30s" or "words 25 punctuation".
+    pub fn settings_label(&self) -> String {
+        let mode = match (self.time_limit_secs, self.word_count) {
+            (Some(secs), _) => format!("time {}s", secs),
+            (None, Some(words)) => format!("words {}", words),
+            (None, None) => String::from("full text"),
+        };
+
+        let mode = if self.punctuation {
+            format!("{} punctuation", mode)
+        } else {
+            mode
+        };
+        let mode = if self.numbers {
+            format!("{} numbers", mode)
+        } else {
+            mode
+        };
+
+        if self.hardcore {
+            format!("{} (hardcore)", mode)
+        } else {
+            mode
+        }
+    }
+}
+
+/// Aggregated numbers over all stored results, shown in the account tab.
+pub struct ProfileStats {
+    pub tests_started: usize, // abandoned tests included
+    pub tests_completed: usize,
+    pub total_time_secs: f64,
+    pub modes: Vec<ModeStats>,
+}
+
+pub struct ModeStats {
+    pub mode: String,
+    pub tests: usize,
+    pub average_wpm: f64,
+    pub best_wpm: i64,
+}
+
+impl ProfileStats {
+    pub fn from_results(results: &[TestResult], tests_started: usize) -> Self {
+        let completed: Vec<&TestResult> = results.iter().filter(|result| result.completed).collect();
+
+        let mut modes: Vec<ModeStats> = Vec::new();
+        for result in &completed {
+            let mode = result.mode_label();
+            match modes.iter_mut().find(|stats| stats.mode == mode) {
+                Some(stats) => {
+                    // running average, so we don't need to keep the sums around
+                    stats.tests += 1;
+                    stats.average_wpm += (result.wpm as f64 - stats.average_wpm) / stats.tests as f64;
+                    stats.best_wpm = stats.best_wpm.max(result.wpm);
+                }
+                None => modes.push(ModeStats {
+                    mode,
+                    tests: 1,
+                    average_wpm: result.wpm as f64,
+                    best_wpm: result.wpm,
+                }),
+            }
+        }
+        modes.sort_by(|a, b| a.mode.cmp(&b.mode));
+
+        Self {
+            tests_started,
+            tests_completed: completed.len(),
+            total_time_secs: results.iter().map(|result| result.duration_secs).sum(),
+            modes,
+        }
+    }
+}
+
+/// Results are only comparable within the same language, test type, length, hardcore, punctuation and numbers setting.
+#[derive(PartialEq)]
+pub struct PbKey {
+    language: String,
+    test_type: TestType,
+    time_limit_secs: Option<u64>,
+    word_count: Option<usize>,
+    hardcore: bool,
+    punctuation: bool,
+    numbers: bool,
+}
+
+impl PbKey {
+    pub fn from_options(options: &AppOptions) -> Self {
+        let time_race = options.is_time_race();
+
+        Self {
+            language: options.result_language().to_string(),
+            test_type: options.test_type,
+            time_limit_secs: time_race.then(|| options.time_limit_secs()),
+            word_count: (!time_race && options.is_word_based())
+                .then(|| options.word_count.count()),
+            hardcore: options.hardcore_enabled,
+            punctuation: options.has_punctuation(),
+            numbers: options.has_numbers(),
+        }
+    }
+
+    fn from_result(result: &TestResult) -> Self {
+        Self {
+            language: result.language.clone(),
+            test_type: result.test_type,
+            time_limit_secs: result.time_limit_secs,
+            word_count: result.word_count,
+            hardcore: result.hardcore,
+            punctuation: result.punctuation,
+            numbers: result.numbers,
+        }
+    }
+}
+
+/// Highest wpm of all completed results with the given key.
+pub fn personal_best(results: &[TestResult], key: &PbKey) -> Option<i64> {
+    results
+        .iter()
+        .filter(|result| result.completed && PbKey::from_result(result) == *key)
+        .map(|result| result.wpm)
+        .max()
+}
+
+/// Local results file, one JSON record per line so new results are simply appended.
+pub struct ResultStore {
+    path: PathBuf,
+}
+
+impl ResultStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// `results.jsonl` in the data directory.
+    pub fn open_default() -> Option<Self> {
+        data_dir().map(|dir| Self::new(dir.join(RESULTS_FILE)))
+    }
+
+    pub fn append(&self, result: &TestResult) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let line = serde_json::to_string(result)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    /// All stored results in the order they were saved, broken lines are skipped.
+    pub fn load_all(&self) -> Vec<TestResult> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                error!("Could not open results {}: {}", self.path.display(), e);
+                return Vec::new();
+            }
+        };
+
+        BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter_map(|(line_number, line)| {
+                let line = line.ok()?;
+                if line.trim().is_empty() {
+                    return None;
+                }
+                match serde_json::from_str::<TestResult>(&line) {
+                    Ok(mut result) => {
+                        result.language = migrate_language_name(result.language);
+                        Some(result)
+                    }
+                    Err(e) => {
+                        warn!("Skipping invalid result in line {}: {}", line_number + 1, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// How many tests were started, restarted and quit ones included. Only finished and failed tests
+/// have a result, so the count is kept in its own file next to the results.
+pub struct TestsStartedStore {
+    path: PathBuf,
+}
+
+impl TestsStartedStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// `tests_started.txt` in the data directory.
+    pub fn open_default() -> Option<Self> {
+        data_dir().map(|dir| Self::new(dir.join(TESTS_STARTED_FILE)))
+    }
+
+    /// The count saved so far, `None` if nothing was counted yet or the file is broken.
+    pub fn load(&self) -> Option<usize> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                error!("Could not read started tests {}: {}", self.path.display(), e);
+                return None;
+            }
+        };
+
+        content
+            .trim()
+            .parse()
+            .map_err(|e| warn!("Invalid started tests {}: {}", self.path.display(), e))
+            .ok()
+    }
+
+    pub fn save(&self, count: usize) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, count.to_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_started_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("monkeytype-oxide-tests-started-{}.txt", std::process::id()));
+        let store = TestsStartedStore::new(&path);
+        assert_eq!(store.load(), None);
+
+        store.save(3).unwrap();
+        assert_eq!(TestsStartedStore::new(&path).load(), Some(3));
+
+        fs::write(&path, "many").unwrap();
+        assert_eq!(store.load(), None);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,34 +1,184 @@
+use log::error;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::Rng;
 use random_word::Lang;
 use serde::Deserialize;
 use std::error::Error;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::app_options::*;
+use crate::custom_text::{load_custom_text, random_window};
+use crate::key_stats::KeyStats;
+use crate::languages::LanguageInfo;
 
+// words generated per batch in a time race
 const TEXT_LEN: usize = 30;
-const BASE_PATH: &str = "util"; 
+pub const BASE_PATH: &str = "util";
+const QUOTES_DIR: &str = "quotes";
+const JOKES_DIR: &str = "jokes";
+const CODE_DIR: &str = "code";
+const CODE_INDENT: &str = "    ";
+// letters that are not a base letter with a mark, so stripping combining marks can't simplify them
+const LAZY_LETTERS: [(char, &str); 9] = [
+    ('ß', "ss"),
+    ('æ', "ae"),
+    ('œ', "oe"),
+    ('ø', "o"),
+    ('ł', "l"),
+    ('đ', "d"),
+    ('ð', "d"),
+    ('þ', "th"),
+    ('ı', "i"),
+];
+// weight of a word without any weak keys in the weakness practice
+const WEAKNESS_BASE_WEIGHT: f64 = 0.01;
 
 enum WordSource<'a> {
     Dictionary(&'a [&'a str]),
     CustomList(Vec<String>),
+    Ranked(Vec<String>), // most frequent word first
 }
 
 #[derive(Deserialize)]
-struct LanguageWords {
+pub struct LanguageWords {
     words: Vec<String>,
-    #[serde(skip)]
-    name: String,
-    #[serde(skip)]
-    bcp47: String,
-    #[serde(skip)]
+    #[serde(default, rename = "orderedByFrequency")]
+    ordered_by_frequency: bool,
+    // pairs of accented letter and its accepted replacements, e.g. ["ä", "ae"]
+    #[serde(default, rename = "additionalAccents")]
     additional_accents: Vec<Vec<String>>,
 }
 
-#[derive(Debug)]
+impl LanguageWords {
+    // only lists ordered by frequency can be weighted by rank
+    fn into_source(self) -> WordSource<'static> {
+        if self.ordered_by_frequency {
+            WordSource::Ranked(self.words)
+        } else {
+            WordSource::CustomList(self.words)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct QuoteCollection {
+    // character ranges (inclusive) of the short, medium, long and thicc groups
+    groups: Vec<[usize; 2]>,
+    quotes: Vec<Quote>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Quote {
+    pub text: String,
+    pub source: String,
+    pub length: usize,
+}
+
+impl QuoteCollection {
+    /// All quotes whose length falls into the group of the given `QuoteLength`.
+    pub fn filter_by_length(&self, length: QuoteLength) -> Vec<&Quote> {
+        let range = length
+            .group_index()
+            .and_then(|index| self.groups.get(index));
+
+        self.quotes
+            .iter()
+            .filter(|quote| match range {
+                Some([min, max]) => (*min..=*max).contains(&quote.length),
+                None => true,
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct JokeCollection {
+    jokes: Vec<Joke>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Joke {
+    pub setup: String,
+    pub punchline: String,
+}
+
+impl Joke {
+    /// Setup and punchline as one line of text to type.
+    pub fn to_text(&self) -> String {
+        normalize_whitespace(&format!("{} {}", self.setup, self.punchline))
+    }
+}
+
+/// The text a test is built from, `source` is set for quotes.
+pub struct GeneratedText {
+    pub text: String,
+    pub source: Option<String>,
+    pub tokens: Vec<TokenKind>, // one per grapheme cluster of code, empty for everything else
+}
+
+impl GeneratedText {
+    fn from_text(text: String) -> Self {
+        Self { text, source: None, tokens: Vec::new() }
+    }
+}
+
+/// Highlighting class of a grapheme in a code snippet.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    String,
+    Number,
+    Comment,
+}
+
+#[derive(Deserialize)]
+pub struct CodeCollection {
+    #[serde(flatten)]
+    syntax: CodeSyntax,
+    snippets: Vec<CodeSnippet>,
+}
+
+// What the highlighting needs to know about a code language
+#[derive(Deserialize, Default)]
+struct CodeSyntax {
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default, rename = "lineComment")]
+    line_comment: Option<String>,
+    #[serde(default, rename = "stringDelimiters")]
+    string_delimiters: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct CodeSnippet {
+    pub name: String,
+    pub code: String,
+}
+
+impl CodeSnippet {
+    /// The code with unix line breaks, spaces instead of tabs and no trailing whitespace.
+    pub fn to_text(&self) -> String {
+        self.code
+            .replace("\r\n", "\n")
+            .replace('\t', CODE_INDENT)
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum WordSetSize {
     Base,
     OneK,
@@ -36,7 +186,7 @@ pub enum WordSetSize {
 }
 
 impl WordSetSize {
-    fn to_suffix(&self) -> &str {
+    pub fn to_suffix(self) -> &'static str {
         match self {
             WordSetSize::Base => "",
             WordSetSize::OneK => "_1k",
@@ -55,92 +205,630 @@ impl WordSetConfig {
         }
     }
 
-    pub fn get_words(&self, language: &str, size: WordSetSize) -> Result<Vec<String>, Box<dyn Error>> {
+    pub fn get_words(&self, language: &str, size: WordSetSize) -> Result<LanguageWords, Box<dyn Error>> {
         let filename = format!("{}{}.json", language, size.to_suffix());
         let file_path = self.base_path.join(filename);
         self.read_words_from_file(file_path)
     }
 
-    fn read_words_from_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<String>, Box<dyn Error>> {
+    pub fn get_quotes(&self, language: &str) -> Result<QuoteCollection, Box<dyn Error>> {
+        let filename = format!("{}.json", language);
+        let file_path = self.base_path.join(QUOTES_DIR).join(filename);
+        let file = File::open(file_path)?;
+        let reader = BufReader::new(file);
+        let quotes: QuoteCollection = serde_json::from_reader(reader)?;
+        Ok(quotes)
+    }
+
+    pub fn get_jokes(&self, language: &str) -> Result<JokeCollection, Box<dyn Error>> {
+        let filename = format!("{}.json", language);
+        let file_path = self.base_path.join(JOKES_DIR).join(filename);
+        let file = File::open(file_path)?;
+        let reader = BufReader::new(file);
+        let jokes: JokeCollection = serde_json::from_reader(reader)?;
+        Ok(jokes)
+    }
+
+    pub fn get_code(&self, language: &str) -> Result<CodeCollection, Box<dyn Error>> {
+        let filename = format!("{}.json", language);
+        let file_path = self.base_path.join(CODE_DIR).join(filename);
+        let file = File::open(file_path)?;
+        let reader = BufReader::new(file);
+        let code: CodeCollection = serde_json::from_reader(reader)?;
+        Ok(code)
+    }
+
+    /// Accent table of one word list of a language, empty if the list has none.
+    pub fn get_additional_accents(
+        &self,
+        language: &str,
+        size: WordSetSize,
+    ) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+        let filename = format!("{}{}.json", language, size.to_suffix());
+        let file = File::open(self.base_path.join(filename))?;
+        let reader = BufReader::new(file);
+        let language: LanguageWords = serde_json::from_reader(reader)?;
+        Ok(language.additional_accents)
+    }
+
+    fn read_words_from_file<P: AsRef<Path>>(&self, path: P) -> Result<LanguageWords, Box<dyn Error>> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
         let language: LanguageWords = serde_json::from_reader(reader)?;
-        Ok(language.words)
+        Ok(language)
     }
 }
 
-pub fn get_sentence(lan: Language, test_type: TestType) -> String {
-    let lang_str = match lan {
-        Language::De => "german",
-        Language::En => "english",
+/// Accent table of a language with every entry in composed form, used for lenient accents and lazy mode.
+/// It comes from the base word list, or from a sized one for languages that only have those.
+pub fn get_additional_accents(language: &LanguageInfo) -> Vec<Vec<String>> {
+    let Some(size) = [WordSetSize::Base, WordSetSize::OneK, WordSetSize::TenK]
+        .into_iter()
+        .find(|size| language.has_word_set(*size))
+    else {
+        return Vec::new();
     };
-    
-    let language = match lan {
-        Language::De => Lang::De,
-        Language::En => Lang::En,
+
+    let config = WordSetConfig::new(&language.dir);
+    match config.get_additional_accents(&language.name, size) {
+        Ok(accents) => accents
+            .into_iter()
+            .map(|entry| entry.iter().map(|s| s.nfc().collect()).collect())
+            .collect(),
+        Err(e) => {
+            error!("Could not load accents for {}: {}", language.name, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Programming languages with a snippet file, sorted by name.
+pub fn list_code_languages() -> Vec<String> {
+    let dir = Path::new(BASE_PATH).join(CODE_DIR);
+    let mut languages: Vec<String> = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .collect(),
+        Err(e) => {
+            error!("Could not read {}: {}", dir.display(), e);
+            Vec::new()
+        }
     };
+    languages.sort();
+    languages
+}
 
-    match test_type {
+// random_word only ships a few languages, the others use their biggest word list for chaos
+fn random_word_lang(language: &LanguageInfo) -> Option<Lang> {
+    match language.language_code().as_deref() {
+        Some("en") => Some(Lang::En),
+        Some("de") => Some(Lang::De),
+        _ => None,
+    }
+}
+
+// Chaos mode without a dictionary and the weakness practice use the biggest list the language has
+fn largest_word_set(language: &LanguageInfo) -> WordSetSize {
+    [WordSetSize::TenK, WordSetSize::OneK, WordSetSize::Base]
+        .into_iter()
+        .find(|size| language.has_word_set(*size))
+        .unwrap_or(WordSetSize::Base)
+}
+
+/// Whether the words of the test come from a list ordered by frequency, Zipf sampling has no effect otherwise.
+pub fn supports_zipf(options: &AppOptions, language: &LanguageInfo) -> bool {
+    match options.test_type {
         TestType::RandomWords => {
-            get_random_sentence(language, WordSource::Dictionary(random_word::all(language)))
+            random_word_lang(language).is_none() && language.is_ranked(largest_word_set(language))
         }
+        TestType::RandomWords1K => language.is_ranked(WordSetSize::OneK),
+        TestType::RandomWords10K => language.is_ranked(WordSetSize::TenK),
+        _ => false,
+    }
+}
+
+/// Text for a test, all randomness comes from `rng` so a seeded rng always gives the same text.
+/// `key_stats` of earlier tests pick the words of a weakness practice.
+pub fn get_sentence(
+    options: &AppOptions,
+    language: &LanguageInfo,
+    key_stats: &KeyStats,
+    rng: &mut StdRng,
+) -> GeneratedText {
+    let lang_str = language.name.as_str();
+    let config = WordSetConfig::new(&language.dir);
+
+    let word_count = if options.is_time_race() {
+        TEXT_LEN
+    } else {
+        options.word_count.count()
+    };
+
+    let mut generated = match options.test_type {
+        TestType::RandomWords => match random_word_lang(language) {
+            Some(lang) => word_text(
+                get_random_words(WordSource::Dictionary(random_word::all(lang)), word_count, None, rng),
+                options,
+                language,
+                rng,
+            ),
+            None => {
+                match config.get_words(lang_str, largest_word_set(language)) {
+                    Ok(words) => word_text(
+                        get_random_words(words.into_source(), word_count, options.zipf_exponent(), rng),
+                        options,
+                        language,
+                        rng,
+                    ),
+                    Err(e) => GeneratedText::from_text(format!("Error loading words: {}", e)),
+                }
+            }
+        },
         TestType::RandomWords1K => {
-            let config = WordSetConfig::new(BASE_PATH);
             match config.get_words(lang_str, WordSetSize::OneK) {
-                Ok(words) => get_random_sentence(language, WordSource::CustomList(words)),
-                Err(e) => format!("Error loading words: {}", e),
+                Ok(words) => word_text(
+                    get_random_words(words.into_source(), word_count, options.zipf_exponent(), rng),
+                    options,
+                    language,
+                    rng,
+                ),
+                Err(e) => GeneratedText::from_text(format!("Error loading words: {}", e)),
             }
         }
         TestType::RandomWords10K => {
-            let config = WordSetConfig::new(BASE_PATH);
             match config.get_words(lang_str, WordSetSize::TenK) {
-                Ok(words) => get_random_sentence(language, WordSource::CustomList(words)),
-                Err(e) => format!("Error loading words: {}", e),
+                Ok(words) => word_text(
+                    get_random_words(words.into_source(), word_count, options.zipf_exponent(), rng),
+                    options,
+                    language,
+                    rng,
+                ),
+                Err(e) => GeneratedText::from_text(format!("Error loading words: {}", e)),
+            }
+        }
+        TestType::Quotes => {
+            match config.get_quotes(lang_str) {
+                Ok(collection) => get_random_quote(&collection, options.quote_length, rng),
+                Err(e) => GeneratedText::from_text(format!("Error loading quotes: {}", e)),
             }
         }
-        TestType::Quotes => String::from("Quotes feature not implemented yet"),
         // TestType::TimeRace => String::from("Time Race feature not implemented yet"),
-        TestType::Jokes => String::from("Jokes feature not implemented yet"),
+        TestType::Jokes => {
+            match config.get_jokes(lang_str) {
+                Ok(collection) => GeneratedText::from_text(get_random_joke(&collection, rng)),
+                Err(e) => GeneratedText::from_text(format!("Error loading jokes: {}", e)),
+            }
+        }
         // TestType::Hardcore => String::from("Hardcore feature not implemented yet"),
+        TestType::Weaknesses => {
+            match config.get_words(lang_str, largest_word_set(language)) {
+                Ok(list) => word_text(
+                    get_weakness_words(list.words, word_count, key_stats, rng),
+                    options,
+                    language,
+                    rng,
+                ),
+                Err(e) => GeneratedText::from_text(format!("Error loading words: {}", e)),
+            }
+        }
+        // code is the same in every test language, it comes with the app
+        TestType::Code => {
+            match WordSetConfig::new(BASE_PATH).get_code(&options.code_language) {
+                Ok(collection) => get_random_snippet(&collection, rng),
+                Err(e) => GeneratedText::from_text(format!("Error loading code: {}", e)),
+            }
+        }
+        TestType::Custom => match &options.custom_text_path {
+            Some(path) => match load_custom_text(path, options) {
+                Ok(text) => GeneratedText::from_text(match options.custom_text_mode {
+                    CustomTextMode::Whole => text,
+                    CustomTextMode::Window => random_window(&text, word_count, rng),
+                    CustomTextMode::Shuffled => {
+                        let words = text.split(' ').map(String::from).collect();
+                        get_random_words(WordSource::CustomList(words), word_count, None, rng).join(" ")
+                    }
+                }),
+                Err(e) => GeneratedText::from_text(format!("Error loading custom text: {}", e)),
+            },
+            None => GeneratedText::from_text(String::from("No custom text selected")),
+        },
+    };
+
+    // Lazy mode types the language without diacritics, unless the language forbids it
+    if options.is_lazy(lang_str) && !language.no_lazy_mode && options.test_type != TestType::Code {
+        generated.text = make_lazy(&generated.text, &get_additional_accents(language));
+    }
+
+    // Composed form, so the text compares equal to what the terminal reports for a key press
+    generated.text = generated.text.nfc().collect();
+    generated
+}
+
+fn get_random_quote(collection: &QuoteCollection, length: QuoteLength, rng: &mut StdRng) -> GeneratedText {
+    match collection.filter_by_length(length).choose(rng) {
+        Some(quote) => {
+            GeneratedText {
+                text: normalize_whitespace(&quote.text),
+                source: Some(quote.source.clone()),
+                tokens: Vec::new(),
+            }
+        }
+        None => GeneratedText::from_text(format!("No {} quotes available", length.to_string().to_lowercase())),
     }
 }
 
-fn get_random_sentence(language: Lang, source: WordSource) -> String {
-    let mut rng = thread_rng();
+fn get_random_snippet(collection: &CodeCollection, rng: &mut StdRng) -> GeneratedText {
+    match collection.snippets.choose(rng) {
+        Some(snippet) => {
+            let text = snippet.to_text();
+            GeneratedText {
+                tokens: highlight_code(&text, &collection.syntax),
+                text,
+                source: Some(snippet.name.clone()),
+            }
+        }
+        None => GeneratedText::from_text(String::from("No code snippets available")),
+    }
+}
 
+// Classify every grapheme of the code: line comments, strings on one line, keywords and numbers
+fn highlight_code(code: &str, syntax: &CodeSyntax) -> Vec<TokenKind> {
+    let graphemes: Vec<&str> = code.graphemes(true).collect();
+    let mut tokens = vec![TokenKind::Plain; graphemes.len()];
+    let comment: Vec<&str> = syntax.line_comment.as_deref().unwrap_or_default().graphemes(true).collect();
+    let is_word_char = |g: &str| g.chars().all(|c| c.is_alphanumeric() || c == '_');
+    let line_end = |from: usize| graphemes[from..].iter().position(|g| *g == "\n").map_or(graphemes.len(), |i| from + i);
+
+    let mut i = 0;
+    while i < graphemes.len() {
+        if !comment.is_empty() && graphemes[i..].starts_with(&comment) {
+            let end = line_end(i);
+            tokens[i..end].fill(TokenKind::Comment);
+            i = end;
+        } else if syntax.string_delimiters.iter().any(|d| d == graphemes[i]) {
+            // up to the same delimiter that is not escaped, an unclosed string ends with its line
+            let line_end = line_end(i);
+            let mut end = i + 1;
+            while end < line_end && graphemes[end] != graphemes[i] {
+                end += if graphemes[end] == "\\" { 2 } else { 1 };
+            }
+            let end = (end + 1).min(line_end);
+            tokens[i..end].fill(TokenKind::String);
+            i = end;
+        } else if is_word_char(graphemes[i]) {
+            let end = graphemes[i..].iter().position(|g| !is_word_char(g)).map_or(graphemes.len(), |n| i + n);
+            let word = graphemes[i..end].concat();
+            if word.starts_with(|c: char| c.is_ascii_digit()) {
+                tokens[i..end].fill(TokenKind::Number);
+            } else if syntax.keywords.contains(&word) {
+                tokens[i..end].fill(TokenKind::Keyword);
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+fn get_random_joke(collection: &JokeCollection, rng: &mut StdRng) -> String {
+    match collection.jokes.choose(rng) {
+        Some(joke) => joke.to_text(),
+        None => String::from("No jokes available"),
+    }
+}
+
+/// Pick `word_count` words. Ranked lists are weighted by Zipf's law with `zipf_exponent` and may repeat
+/// words, everything else is sampled uniformly without repeats.
+fn get_random_words(
+    source: WordSource,
+    word_count: usize,
+    zipf_exponent: Option<f64>,
+    rng: &mut StdRng,
+) -> Vec<String> {
     // Choose words based on source
-    let selected_words = match source {
-        WordSource::Dictionary(word_list) => {
+    match (source, zipf_exponent) {
+        (WordSource::Ranked(words), Some(exponent)) if !words.is_empty() => {
+            // the word of rank r is drawn with a weight of 1 / r^s
+            let weights = (1..=words.len()).map(|rank| 1.0 / (rank as f64).powf(exponent));
+            match WeightedIndex::new(weights) {
+                Ok(distribution) => (0..word_count)
+                    .map(|_| words[distribution.sample(rng)].clone())
+                    .collect(),
+                Err(e) => {
+                    error!("Invalid word weights: {}", e);
+                    words.choose_multiple(rng, word_count).cloned().collect()
+                }
+            }
+        }
+        (WordSource::Dictionary(word_list), _) => {
             word_list
-                .choose_multiple(&mut rng, TEXT_LEN)
-                .cloned()
-                .collect::<Vec<&str>>()
-                .join(" ")
+                .choose_multiple(rng, word_count)
+                .map(|word| word.to_string())
+                .collect()
         }
-        WordSource::CustomList(words) => {
+        (WordSource::CustomList(words), _) | (WordSource::Ranked(words), _) => {
             words
-                .choose_multiple(&mut rng, TEXT_LEN)
+                .choose_multiple(rng, word_count)
                 .cloned()
-                .collect::<Vec<String>>()
-                .join(" ")
+                .collect()
         }
-    };
+    }
+}
 
-    // Handle umlauts 
-    if language == Lang::De {
-        replace_umlauts(selected_words)
+/// Pick words with replacement, weighted by how many of the user's weak chars and bigrams they contain.
+/// Without recorded errors every word is equally likely.
+fn get_weakness_words(words: Vec<String>, word_count: usize, stats: &KeyStats, rng: &mut StdRng) -> Vec<String> {
+    // the base weight keeps some variety, words without weak keys still show up now and then
+    let weights = words.iter().map(|word| WEAKNESS_BASE_WEIGHT + stats.word_score(word));
+    match WeightedIndex::new(weights) {
+        Ok(distribution) => (0..word_count)
+            .map(|_| words[distribution.sample(rng)].clone())
+            .collect(),
+        Err(e) => {
+            error!("Invalid word weights: {}", e);
+            words.choose_multiple(rng, word_count).cloned().collect()
+        }
+    }
+}
+
+// Text of the random word test types, with the modifiers of the options applied
+fn word_text(
+    words: Vec<String>,
+    options: &AppOptions,
+    language: &LanguageInfo,
+    rng: &mut StdRng,
+) -> GeneratedText {
+    let words = if options.has_numbers() {
+        add_numbers(words, options.numbers_percent as f64 / 100.0, language, rng)
     } else {
-        selected_words
+        words
+    };
+    let words = if options.has_punctuation() {
+        add_punctuation(words, options.punctuation.probability(), language, rng)
+    } else {
+        words
+    };
+    GeneratedText::from_text(words.join(" "))
+}
+
+/// Capitalise sentence starts and attach punctuation marks to about `probability` of the words.
+/// Every batch ends a sentence, so batches appended in a time race still read like prose.
+fn add_punctuation(
+    words: Vec<String>,
+    probability: f64,
+    language: &LanguageInfo,
+    rng: &mut StdRng,
+) -> Vec<String> {
+    let code = language.language_code();
+    let (open_quote, close_quote) = match code.as_deref() {
+        Some("de") => ("„", "“"),
+        _ => ("\"", "\""),
+    };
+    // spanish opens questions and exclamations with an inverted mark
+    let inverted_marks = code.as_deref() == Some("es");
+
+    let last = words.len().saturating_sub(1);
+    let mut sentence_start = true;
+    let mut result = Vec::with_capacity(words.len());
+
+    for (i, word) in words.into_iter().enumerate() {
+        let mut word = if sentence_start { capitalize(&word) } else { word };
+        sentence_start = false;
+
+        if i == last {
+            word.push('.');
+        } else if rng.gen_bool(probability) {
+            word = match rng.gen_range(0..10) {
+                0..=2 => format!("{},", word),
+                3 | 4 => {
+                    sentence_start = true;
+                    format!("{}.", word)
+                }
+                5 => {
+                    sentence_start = true;
+                    if inverted_marks { format!("¿{}?", word) } else { format!("{}?", word) }
+                }
+                6 => {
+                    sentence_start = true;
+                    if inverted_marks { format!("¡{}!", word) } else { format!("{}!", word) }
+                }
+                7 => format!("{}{}{}", open_quote, word, close_quote),
+                8 => format!("({})", word),
+                _ => format!("{}{}", word, if rng.gen_bool(0.5) { ';' } else { ':' }),
+            };
+        }
+        result.push(word);
     }
+    result
 }
 
-fn replace_umlauts(sentence: String) -> String {
-    sentence
-        .replace("ä", "ae")
-        .replace("ö", "oe")
-        .replace("ü", "ue")
-        .replace("Ä", "Ae")
-        .replace("Ö", "Oe")
-        .replace("Ü", "Ue")
-        .replace("ß", "ss")
-}
\ No newline at end of file
+/// Replace about `share` of the words by integers, decimals, dates or phone numbers.
+fn add_numbers(words: Vec<String>, share: f64, language: &LanguageInfo, rng: &mut StdRng) -> Vec<String> {
+    let german_style = language.language_code().as_deref() == Some("de");
+
+    words
+        .into_iter()
+        .map(|word| {
+            if !rng.gen_bool(share) {
+                return word;
+            }
+            match rng.gen_range(0..10) {
+                // short integers are the most common in real data
+                0..=3 => rng.gen_range(0..1000).to_string(),
+                4 => rng.gen_range(1000..100_000).to_string(),
+                5 | 6 => {
+                    let separator = if german_style { ',' } else { '.' };
+                    format!("{}{}{:02}", rng.gen_range(0..1000), separator, rng.gen_range(0..100))
+                }
+                7 | 8 => {
+                    let (day, month, year) = (rng.gen_range(1..=28), rng.gen_range(1..=12), rng.gen_range(1950..=2030));
+                    if german_style {
+                        format!("{:02}.{:02}.{}", day, month, year)
+                    } else {
+                        format!("{:02}/{:02}/{}", month, day, year)
+                    }
+                }
+                _ => format!(
+                    "{:03}-{:03}-{:04}",
+                    rng.gen_range(100..1000),
+                    rng.gen_range(0..1000),
+                    rng.gen_range(0..10_000)
+                ),
+            }
+        })
+        .collect()
+}
+
+/// First letter in upper case, the rest untouched.
+pub fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Collapse line breaks and repeated spaces, the wrapping only knows single ascii spaces
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Replace accented letters by plain ones: first from the language's accent table, like "ae" for "ä",
+/// then the letters of `LAZY_LETTERS`, then by dropping combining marks.
+fn make_lazy(text: &str, accents: &[Vec<String>]) -> String {
+    let text: String = text.nfc().collect();
+
+    text.graphemes(true)
+        .map(|g| {
+            // the tables only list lowercase letters
+            let lowercase = g.to_lowercase();
+            let replacement = accents
+                .iter()
+                .find(|entry| entry.first() == Some(&lowercase))
+                .and_then(|entry| entry.get(1).cloned())
+                .or_else(|| {
+                    LAZY_LETTERS
+                        .iter()
+                        .find(|(letter, _)| letter.to_string() == lowercase)
+                        .map(|(_, plain)| plain.to_string())
+                });
+
+            match replacement {
+                Some(plain) if lowercase != g => capitalize(&plain),
+                Some(plain) => plain,
+                None => g.nfd().filter(|c| !is_combining_mark(*c)).collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    use crate::languages::LanguageRegistry;
+
+    fn english() -> LanguageInfo {
+        LanguageRegistry::discover_in(&[PathBuf::from(BASE_PATH)]).resolve("english")
+    }
+
+    fn generate(options: &AppOptions, seed: u64) -> String {
+        get_sentence(options, &english(), &KeyStats::default(), &mut StdRng::seed_from_u64(seed)).text
+    }
+
+    fn assert_reproducible(options: &AppOptions) {
+        let text = generate(options, 42);
+        assert!(!text.starts_with("Error"), "{}", text);
+        assert_eq!(text, generate(options, 42));
+    }
+
+    #[test]
+    fn same_seed_same_words() {
+        for test_type in [TestType::RandomWords, TestType::RandomWords1K, TestType::RandomWords10K] {
+            let options = AppOptions {
+                test_type,
+                ..AppOptions::default()
+            };
+            assert_reproducible(&options);
+        }
+
+        let options = AppOptions {
+            test_type: TestType::RandomWords1K,
+            word_sampling: WordSampling::Zipf,
+            punctuation: PunctuationFrequency::High,
+            numbers_percent: 20,
+            ..AppOptions::default()
+        };
+        assert_reproducible(&options);
+        assert_ne!(generate(&options, 42), generate(&options, 43));
+    }
+
+    #[test]
+    fn same_seed_same_quote() {
+        let options = AppOptions {
+            test_type: TestType::Quotes,
+            ..AppOptions::default()
+        };
+        assert_reproducible(&options);
+    }
+
+    #[test]
+    fn same_seed_same_custom_text() {
+        let path = std::env::temp_dir().join(format!("monkeytype-oxide-seed-{}.txt", std::process::id()));
+        let words: Vec<String> = (0..200).map(|i| format!("word{}", i)).collect();
+        std::fs::write(&path, words.join(" ")).unwrap();
+
+        for custom_text_mode in [CustomTextMode::Window, CustomTextMode::Shuffled] {
+            let options = AppOptions {
+                test_type: TestType::Custom,
+                custom_text_path: Some(path.clone()),
+                custom_text_mode,
+                ..AppOptions::default()
+            };
+            assert_reproducible(&options);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // a language that only comes as a top 1k list, like many user added ones. Remove `language.dir` when done
+    fn only_1k_language(name: &str) -> LanguageInfo {
+        let dir = std::env::temp_dir().join(format!("monkeytype-oxide-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let list = format!(
+            r#"{{"name": "{}_1k", "words": ["b\u00e4r", "k\u00e4se"], "additionalAccents": [["\u00e4", "ae"]]}}"#,
+            name
+        );
+        std::fs::write(dir.join(format!("{}_1k.json", name)), list).unwrap();
+        LanguageRegistry::discover_in(&[dir]).resolve(name)
+    }
+
+    #[test]
+    fn accents_of_a_language_without_base_list() {
+        let language = only_1k_language("accents");
+        assert_eq!(get_additional_accents(&language), [["\u{e4}", "ae"]]);
+        std::fs::remove_dir_all(&language.dir).unwrap();
+    }
+
+    #[test]
+    fn lazy_mode_of_a_language_without_base_list() {
+        let language = only_1k_language("lazy");
+        let options = AppOptions {
+            test_type: TestType::RandomWords1K,
+            test_language: language.name.clone(),
+            lazy_mode: vec![language.name.clone()],
+            ..AppOptions::default()
+        };
+        let text = get_sentence(&options, &language, &KeyStats::default(), &mut StdRng::seed_from_u64(1)).text;
+        std::fs::remove_dir_all(&language.dir).unwrap();
+
+        assert!(!text.contains('\u{e4}'), "{}", text);
+        assert!(text.split(' ').all(|word| word == "baer" || word == "kaese"), "{}", text);
+    }
+}
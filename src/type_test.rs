@@ -1,12 +1,44 @@
 use log::error;
-use ratatui::style::{Color, Style};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::{Duration, Instant};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::text_gen::{capitalize, get_additional_accents, get_sentence, TokenKind};
+
+use crate::app_options::{AppOptions, BackspacePolicy, TestType};
+use crate::key_stats::KeyStats;
+use crate::languages::LanguageInfo;
+
+// In a time race new words are appended once fewer graphemes than this are left
+const EXTEND_THRESHOLD: usize = 60;
+// Spaces per indentation level in code tests
+const TAB_WIDTH: usize = 4;
+// Letters typed past the end of a word beyond this are dropped
+const MAX_EXTRA_CHARS: usize = 20;
+
+// Spacing accents a terminal may report for a dead key, paired with the combining mark they stand for
+const DEAD_KEYS: [(char, char); 6] = [
+    ('´', '\u{301}'),
+    ('`', '\u{300}'),
+    ('^', '\u{302}'),
+    ('¨', '\u{308}'),
+    ('~', '\u{303}'),
+    ('¸', '\u{327}'),
+];
+
+enum GraphemeMatch {
+    Correct,
+    Slip, // accepted in lenient accent mode, but not the accented target
+    Wrong,
+    Incomplete, // could still become the target with the next char
+}
 
-use crate::text_gen::get_sentence;
-
-use crate::app_options::{Language, TestType};
-
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TestDataPerSecond {
     pub mistakes: usize,
     pub wpm: i64,
@@ -15,82 +47,345 @@ pub struct TestDataPerSecond {
 }
 
 pub struct TypingTest {
-    language: Language,
-    test_type: TestType,
+    options: AppOptions,
+    language: LanguageInfo,
+    pub seed: u32, // the text of a test is determined by its seed and options
+    rng: StdRng, // seeded with `seed`, also used for the words added in a time race
     pub target_text: String,
-    pub colored_chars: Vec<(char, Style)>,
-    pub user_input: String,
+    pub source: Option<String>, // author / work of a quote
+    target_graphemes: Vec<String>, // target_text split into grapheme clusters, `index` points into this
+    pub colored_chars: Vec<(String, Style)>, // one entry per grapheme cluster
+    untyped_styles: Vec<Style>, // style of every grapheme before it is typed, code is highlighted
+    pub user_input: Vec<String>, // typed grapheme for every position before `index`, empty for skipped ones
+    pub extra_chars: BTreeMap<usize, Vec<String>>, // letters typed past a word, keyed by the index of the whitespace after it
+    pending_input: String, // start of a multi-char cluster that is not complete yet
     pub index: usize,
     pub mistakes: usize,
+    pub accent_slips: usize,
+    pub missed_chars: usize, // letters left out by skipping to the next word
+    pub extra_chars_typed: usize,
+    accents: Vec<Vec<String>>, // accent table of the language, only loaded in lenient mode
     pub total_chars_tipped: usize,
     // pub total_words: usize,
     pub text_finished: bool,
     pub start_time: Option<Instant>,
     pub end_time: Option<Instant>,
     pub correct_words_chars: i32,
+    scored_words: BTreeMap<usize, i32>, // chars each correct word added, keyed by the index of its last grapheme
     last_word_start: usize,
     pub test_data_history: Vec<TestDataPerSecond>,
     mistakes_in_current_second: usize,
+    pub key_stats: KeyStats, // errors per target char and bigram of this test
+    practice_stats: KeyStats, // stats of earlier tests, only kept for a weakness practice to extend it
 }
 impl TypingTest {
-    pub fn new(lan: Language, test_type: TestType) -> Self {
-        let text = get_sentence(lan.clone(), test_type.clone());
-        let colored_chars = text
-            .chars()
-            .map(|c| (c, Style::default().fg(Color::DarkGray)))
+    /// `key_stats` are the stats of all earlier tests, used to pick the words of a weakness practice.
+    pub fn new(options: &AppOptions, language: &LanguageInfo, key_stats: &KeyStats) -> Self {
+        let seed = options.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(u64::from(seed));
+        let generated = get_sentence(options, language, key_stats, &mut rng);
+        let text = generated.text;
+        let target_graphemes: Vec<String> = text.graphemes(true).map(String::from).collect();
+        let untyped_styles = untyped_styles(&generated.tokens, target_graphemes.len());
+        let colored_chars = target_graphemes
+            .iter()
+            .cloned()
+            .zip(untyped_styles.iter().copied())
             .collect();
 
         Self {
-            language: lan,
-            test_type: test_type,
+            options: options.clone(),
+            language: language.clone(),
+            seed,
+            rng,
             target_text: text,
+            source: generated.source,
+            target_graphemes,
             colored_chars,
-            user_input: String::new(),
+            untyped_styles,
+            user_input: Vec::new(),
+            extra_chars: BTreeMap::new(),
+            pending_input: String::new(),
             index: 0,
             mistakes: 0,
+            accent_slips: 0,
+            missed_chars: 0,
+            extra_chars_typed: 0,
+            accents: if options.lenient_accents {
+                get_additional_accents(language)
+            } else {
+                Vec::new()
+            },
             total_chars_tipped: 0,
             // total_words: words_amount,
             text_finished: false,
             start_time: None,
             end_time: None,
             correct_words_chars: 0,
+            scored_words: BTreeMap::new(),
             last_word_start: 0,
             test_data_history: Vec::new(),
             mistakes_in_current_second: 0,
+            key_stats: KeyStats::default(),
+            practice_stats: if options.test_type == TestType::Weaknesses {
+                key_stats.clone()
+            } else {
+                KeyStats::default()
+            },
         }
     }
 
     pub fn type_char(&mut self, c: char) {
-        if let Some(target_char) = self.target_text.chars().nth(self.index) {
+        if let Some(target) = self.target_graphemes.get(self.index).cloned() {
+            if self.pending_input.is_empty() {
+                // space moves on to the next word, unless nothing of the word was typed yet
+                if c == ' ' && !is_whitespace(&target) {
+                    if self.word_start(self.index) < self.index {
+                        self.skip_word();
+                    }
+                    return;
+                }
+                // letters past the end of a word don't move the cursor, they are shown after the word
+                if !c.is_whitespace() && self.is_word_end(self.index) {
+                    self.type_extra(c);
+                    return;
+                }
+            }
+
             if self.index == 0 {
                 self.start_timer();
             }
 
-            self.user_input.push(c);
-            self.total_chars_tipped += 1;
+            // Clusters like a letter with a combining accent can arrive as several chars,
+            // wait until the cluster is complete or can no longer match
+            self.pending_input.push(c);
+            let alternatives = self.accent_alternatives(&target);
+            let result = match match_grapheme(&self.pending_input, &target, &alternatives) {
+                GraphemeMatch::Incomplete => return,
+                GraphemeMatch::Wrong if self.pending_input.chars().count() > 1 => {
+                    // The chars before may be a grapheme of their own, like "a" for "ä" that
+                    // was waiting for an "e". Commit them and pass the new char on to the next grapheme
+                    self.pending_input.pop();
+                    let previous = std::mem::take(&mut self.pending_input);
+                    let result = resolve_grapheme(&previous, &target, &alternatives);
+                    self.commit_grapheme(previous, &target, result);
+                    self.type_char(c);
+                    return;
+                }
+                result => result,
+            };
+            let typed = std::mem::take(&mut self.pending_input);
+            self.commit_grapheme(typed, &target, result);
+        }
+    }
 
-            let is_current_char_correct = c == target_char;
+    /// Commit what waits on the last grapheme once no more input follows, nothing comes after it
+    /// to complete or end it. A lone dead key keeps waiting for its letter.
+    pub fn resolve_pending(&mut self) {
+        let is_last = self.index + 1 == self.target_graphemes.len();
+        if !is_last || self.pending_input.is_empty() || is_dead_key(&self.pending_input) {
+            return;
+        }
 
-            if let Some((_, style)) = self.colored_chars.get_mut(self.index) {
-                *style = if is_current_char_correct {
-                    Style::default().fg(Color::Green)
-                } else {
-                    Style::default().fg(Color::Red)
-                };
-            }
+        let target = self.target_graphemes[self.index].clone();
+        let typed = std::mem::take(&mut self.pending_input);
+        let result = resolve_grapheme(&typed, &target, &self.accent_alternatives(&target));
+        self.commit_grapheme(typed, &target, result);
+    }
 
-            if !is_current_char_correct {
+    fn commit_grapheme(&mut self, typed: String, target: &str, result: GraphemeMatch) {
+        self.total_chars_tipped += 1;
+
+        if let Some((_, style)) = self.colored_chars.get_mut(self.index) {
+            *style = match result {
+                GraphemeMatch::Correct => Style::default().fg(Color::Green),
+                GraphemeMatch::Slip => Style::default().fg(Color::Yellow),
+                _ => Style::default().fg(Color::Red),
+            };
+        }
+
+        let previous = self.index.checked_sub(1).map(|i| self.target_graphemes[i].as_str());
+        let wrong = !matches!(result, GraphemeMatch::Correct | GraphemeMatch::Slip);
+        self.key_stats.record(previous, target, wrong);
+
+        match result {
+            GraphemeMatch::Correct => {}
+            GraphemeMatch::Slip => self.accent_slips += 1,
+            _ => {
                 self.mistakes += 1;
                 self.mistakes_in_current_second += 1;
             }
+        }
+
+        // store the target for accepted input, the raw input may be decomposed or a dead key sequence
+        match result {
+            GraphemeMatch::Correct | GraphemeMatch::Slip => self.user_input.push(target.to_string()),
+            _ => self.user_input.push(typed.nfc().collect()),
+        }
+        self.finish_word();
+        if target == "\n" && matches!(result, GraphemeMatch::Correct) && self.options.code_auto_indent {
+            self.fill_indentation();
+        }
+        self.extend_text_if_needed();
+    }
+
+    // Accept the indentation after a line break without typing it, it is left out of the wpm
+    fn fill_indentation(&mut self) {
+        while self.target_graphemes.get(self.index).is_some_and(|g| g == " ") {
+            if let Some((_, style)) = self.colored_chars.get_mut(self.index) {
+                *style = Style::default().fg(Color::Green);
+            }
+            self.user_input.push(String::from(" "));
+            self.index += 1;
+        }
+        self.last_word_start = self.index;
+    }
+
+    // Mark the rest of the word as missed and type the whitespace after it, a line break at the end of a code line
+    fn skip_word(&mut self) {
+        let word_end = (self.index..self.target_graphemes.len())
+            .find(|i| is_whitespace(&self.target_graphemes[*i]))
+            .unwrap_or(self.target_graphemes.len());
+
+        while self.index < word_end {
+            if let Some((_, style)) = self.colored_chars.get_mut(self.index) {
+                *style = self.untyped_styles[self.index].add_modifier(Modifier::UNDERLINED);
+            }
+            self.user_input.push(String::new());
+            self.missed_chars += 1;
+            self.index += 1;
+        }
+
+        // the last word has no whitespace after it, skipping it ends the text
+        if let Some(whitespace) = self.target_graphemes.get(self.index).and_then(|g| g.chars().next()) {
+            self.type_char(whitespace);
+        }
+    }
+
+    fn type_extra(&mut self, c: char) {
+        let extras = self.extra_chars.entry(self.index).or_default();
+        if extras.len() >= MAX_EXTRA_CHARS {
+            return;
+        }
+        extras.push(c.to_string());
+
+        self.total_chars_tipped += 1;
+        self.mistakes += 1;
+        self.mistakes_in_current_second += 1;
+        self.extra_chars_typed += 1;
+    }
+
+    /// Tab types the indentation up to the next tab stop, anywhere else it is a wrong char.
+    pub fn type_tab(&mut self) {
+        if self.target_graphemes.get(self.index).is_none_or(|g| g != " ") {
+            self.type_char('\t');
+            return;
+        }
+
+        let mut column = self.index
+            - self.target_graphemes[..self.index]
+                .iter()
+                .rposition(|g| g == "\n")
+                .map_or(0, |i| i + 1);
+        loop {
+            self.type_char(' ');
+            column += 1;
+            let next_is_space = self.target_graphemes.get(self.index).is_some_and(|g| g == " ");
+            if column % TAB_WIDTH == 0 || !next_is_space {
+                break;
+            }
+        }
+    }
+
+    // A word ends with the first whitespace after it, runs of indentation belong to the next word
+    fn is_word_end(&self, index: usize) -> bool {
+        let is_whitespace_at = |i: usize| self.target_graphemes.get(i).is_some_and(|g| is_whitespace(g));
+        is_whitespace_at(index) && index > 0 && !is_whitespace_at(index - 1)
+    }
 
-            self.check_for_correct_word(target_char, is_current_char_correct);
+    // Whether the backspace policy lets the next backspace through
+    fn can_backspace(&self) -> bool {
+        match self.options.backspace_policy {
+            BackspacePolicy::Free => true,
+            // the cursor may not go back past the start of its word into a finished one
+            BackspacePolicy::WithinWord => {
+                !self.pending_input.is_empty()
+                    || self.extra_chars.contains_key(&self.index)
+                    || self.word_start(self.index) < self.index
+            }
+            BackspacePolicy::Disabled => false,
         }
     }
 
-    fn check_for_correct_word(&mut self, target_char: char, is_current_char_correct: bool) {
-        // Check if end of word reached (whitespace or end of text)
-        let is_word_end = target_char == ' ' || self.index == self.target_text.len() - 1;
+    // First grapheme after the last whitespace before `index`
+    fn word_start(&self, index: usize) -> usize {
+        self.target_graphemes[..index.min(self.target_graphemes.len())]
+            .iter()
+            .rposition(|g| is_whitespace(g))
+            .map_or(0, |i| i + 1)
+    }
+
+    // What else counts for an accented target in lenient mode: the bare base letter
+    // and the replacements from the language's accent table
+    fn accent_alternatives(&self, target: &str) -> Vec<String> {
+        if !self.options.lenient_accents {
+            return Vec::new();
+        }
+
+        let mut alternatives = Vec::new();
+
+        let decomposed: Vec<char> = target.nfd().collect();
+        if decomposed.len() > 1 && decomposed[1..].iter().all(|c| is_combining_mark(*c)) {
+            alternatives.push(decomposed[0].to_string());
+        }
+
+        // the tables only list lowercase letters
+        let lowercase = target.to_lowercase();
+        let is_uppercase = lowercase != target;
+        for entry in self.accents.iter().filter(|entry| entry.first() == Some(&lowercase)) {
+            for replacement in &entry[1..] {
+                if is_uppercase {
+                    alternatives.push(capitalize(replacement));
+                } else {
+                    alternatives.push(replacement.clone());
+                }
+            }
+        }
+
+        alternatives
+    }
+
+    // Keep a time race supplied with text, so it never runs out before the clock does.
+    // New text is appended in chunks, the text typed so far stays as it is
+    fn extend_text_if_needed(&mut self) {
+        if !self.options.is_time_race() {
+            return;
+        }
+
+        let remaining = self.target_graphemes.len().saturating_sub(self.index);
+        if remaining >= EXTEND_THRESHOLD {
+            return;
+        }
+
+        // word tests get a new batch of words, everything else starts over with a new quote, joke or snippet
+        let separator = if self.options.test_type == TestType::Code { "\n" } else { " " };
+        let generated = get_sentence(&self.options, &self.language, &self.practice_stats, &mut self.rng);
+        let graphemes: Vec<&str> = std::iter::once(separator).chain(generated.text.graphemes(true)).collect();
+        let mut tokens = vec![TokenKind::Plain];
+        tokens.extend(generated.tokens);
+        for (g, style) in graphemes.iter().zip(untyped_styles(&tokens, graphemes.len())) {
+            self.target_graphemes.push(g.to_string());
+            self.colored_chars.push((g.to_string(), style));
+            self.untyped_styles.push(style);
+        }
+        self.target_text.push_str(separator);
+        self.target_text.push_str(&generated.text);
+    }
+
+    // Score the word once its whitespace or the last char of the text is typed. A word counts
+    // when every letter was typed right, none skipped and nothing typed past it
+    fn finish_word(&mut self) {
+        let is_word_end = self.is_word_end(self.index) || self.index == self.target_graphemes.len() - 1;
 
         if is_word_end {
             // Sicherheitscheck für Indizes
@@ -99,30 +394,15 @@ impl TypingTest {
                 self.last_word_start = self.index;
             }
 
-            // Safe extraction
-            let target_word =
-                match self.safe_extract_word(&self.target_text, self.last_word_start, self.index) {
-                    Ok(word) => word,
-                    Err(e) => {
-                        error!("Warning: Could not extract target word: {}", e);
-                        String::new()
-                    }
-                };
-
-            let user_word =
-                match self.safe_extract_word(&self.user_input, self.last_word_start, self.index) {
-                    Ok(word) => word,
-                    Err(e) => {
-                        error!("Warning: Could not extract user word: {}", e);
-                        String::new()
-                    }
-                };
-
-            // Compare Words
-            let word_correct = target_word == user_word;
+            // Compare Words, both sides are grapheme slices so no char borders can be hit
+            let target_word = &self.target_graphemes[self.last_word_start..=self.index];
+            let user_word = self.user_input.get(self.last_word_start..=self.index);
+            let word_correct = user_word == Some(target_word) && !self.extra_chars.contains_key(&self.index);
 
             if word_correct {
-                self.correct_words_chars += target_word.chars().count() as i32;
+                let chars = target_word.len() as i32;
+                self.correct_words_chars += chars;
+                self.scored_words.insert(self.index, chars);
             }
 
             self.last_word_start = self.index + 1;
@@ -131,48 +411,76 @@ impl TypingTest {
         self.index += 1;
     }
 
-    fn safe_extract_word(&self, text: &str, start: usize, end: usize) -> Result<String, String> {
-        if start > end {
-            return Err("Start index greater than end index".to_string());
+    pub fn backspace(&mut self) {
+        if !self.can_backspace() {
+            return;
         }
-        if start >= text.len() {
-            return Err("Start index out of bounds".to_string());
+
+        // drop an unfinished cluster first
+        if !self.pending_input.is_empty() {
+            self.pending_input.clear();
+            return;
         }
 
-        // Find safe Char-Border
-        let safe_start = text
-            .char_indices()
-            .find(|(i, _)| *i >= start)
-            .map(|(i, _)| i)
-            .unwrap_or(start);
+        // extra letters sit between the word and the cursor
+        if let Some(extras) = self.extra_chars.get_mut(&self.index) {
+            extras.pop();
+            if extras.is_empty() {
+                self.extra_chars.remove(&self.index);
+            }
+            return;
+        }
 
-        let safe_end = std::cmp::min(
-            text.char_indices()
-                .find(|(i, _)| *i > end)
-                .map(|(i, _)| i)
-                .unwrap_or(text.len()),
-            text.len(),
-        );
+        if self.user_input.pop().is_some() && self.index > 0 {
+            self.index -= 1;
+            self.colored_chars[self.index].1 = self.untyped_styles[self.index];
+            // the word is open again, it only counts once it is finished again
+            if let Some(chars) = self.scored_words.remove(&self.index) {
+                self.correct_words_chars -= chars;
+            }
 
-        // Safe Extraction of string slice
-        match text.get(safe_start..safe_end) {
-            Some(slice) => Ok(slice.to_string()),
-            None => Err("Could not extract word slice".to_string()),
+            // back into a skipped word, the cursor goes to the first letter that was left out
+            while self.user_input.last().is_some_and(String::is_empty) {
+                self.user_input.pop();
+                self.missed_chars -= 1;
+                self.index -= 1;
+                self.colored_chars[self.index].1 = self.untyped_styles[self.index];
+            }
+            self.last_word_start = self.word_start(self.index);
         }
     }
 
-    pub fn backspace(&mut self) {
-        if !self.user_input.is_empty() {
-            self.user_input.pop();
-            if self.index > 0 {
-                self.index -= 1;
-                if let Some((_, style)) = self.colored_chars.get_mut(self.index) {
-                    *style = Style::default().fg(Color::DarkGray);
-                }
-            }
+    /// Delete what was typed of the current word, or the whole word before it when nothing is typed yet.
+    pub fn delete_word(&mut self) {
+        let nothing_typed = self.pending_input.is_empty() && !self.extra_chars.contains_key(&self.index);
+        let mut start = self.word_start(self.index);
+        if start == self.index && nothing_typed {
+            // the whitespace before the cursor goes along with the previous word
+            start = (0..self.index)
+                .rev()
+                .find(|i| !is_whitespace(&self.target_graphemes[*i]))
+                .map_or(0, |i| self.word_start(i));
+        }
+
+        while self.can_backspace()
+            && (self.index > start
+                || self.extra_chars.contains_key(&self.index)
+                || !self.pending_input.is_empty())
+        {
+            self.backspace();
         }
     }
 
+    /// Length of the target text in grapheme clusters.
+    pub fn text_len(&self) -> usize {
+        self.target_graphemes.len()
+    }
+
+    /// The options this test was generated with.
+    pub fn options(&self) -> &AppOptions {
+        &self.options
+    }
+
     pub fn accuracy(&self) -> f64 {
         if self.total_chars_tipped == 0 {
             100.0
@@ -183,15 +491,38 @@ impl TypingTest {
     }
 
     pub fn progress(&self) -> u16 {
-        if self.target_text.is_empty() {
+        let total_words = self.total_words();
+        if total_words == 0 {
             0
         } else {
-            ((self.index as f64 / self.target_text.len() as f64) * 100.0) as u16
+            ((self.words_completed() as f64 / total_words as f64) * 100.0) as u16
         }
     }
 
-    pub fn reset(&mut self, lan: Language, test_type: TestType) {
-        let new_test = TypingTest::new(lan, test_type);
+    /// Words whose last char has been typed, a word counts once the cursor moved past it.
+    pub fn words_completed(&self) -> usize {
+        let passed_word_ends = (0..self.index.min(self.target_graphemes.len()))
+            .filter(|i| self.is_word_end(*i))
+            .count();
+
+        if self.is_text_complete() {
+            passed_word_ends + 1
+        } else {
+            passed_word_ends
+        }
+    }
+
+    pub fn total_words(&self) -> usize {
+        self.target_text.split_whitespace().count()
+    }
+
+    /// True once the last char of the text has been typed.
+    pub fn is_text_complete(&self) -> bool {
+        !self.target_graphemes.is_empty() && self.index >= self.target_graphemes.len()
+    }
+
+    pub fn reset(&mut self, options: &AppOptions, language: &LanguageInfo, key_stats: &KeyStats) {
+        let new_test = TypingTest::new(options, language, key_stats);
         *self = new_test;
     }
 
@@ -283,3 +614,329 @@ impl TypingTest {
         &self.test_data_history
     }
 }
+
+// Compare typed chars against one target grapheme, independent of composed or decomposed form
+fn match_grapheme(typed: &str, target: &str, alternatives: &[String]) -> GraphemeMatch {
+    let composed: String = compose_dead_key(typed).nfc().collect();
+    let typed: String = composed.nfd().collect();
+    let target: String = target.nfd().collect();
+
+    if typed == target {
+        GraphemeMatch::Correct
+    } else if target.starts_with(&typed)
+        || is_dead_key_for(&typed, &target)
+        || alternatives
+            .iter()
+            .any(|alt| alt.len() > composed.len() && alt.starts_with(&composed))
+    {
+        GraphemeMatch::Incomplete
+    } else if alternatives.contains(&composed) {
+        GraphemeMatch::Slip
+    } else {
+        GraphemeMatch::Wrong
+    }
+}
+
+// Like match_grapheme, for input that can't grow any more
+fn resolve_grapheme(typed: &str, target: &str, alternatives: &[String]) -> GraphemeMatch {
+    let composed: String = compose_dead_key(typed).nfc().collect();
+
+    if composed == target.nfc().collect::<String>() {
+        GraphemeMatch::Correct
+    } else if alternatives.contains(&composed) {
+        GraphemeMatch::Slip
+    } else {
+        GraphemeMatch::Wrong
+    }
+}
+
+// A dead key followed by a letter becomes the letter with the combining mark
+fn compose_dead_key(typed: &str) -> String {
+    let mut chars = typed.chars();
+    if let (Some(accent), Some(letter), None) = (chars.next(), chars.next(), chars.next()) {
+        if let Some((_, combining)) = DEAD_KEYS.iter().find(|(key, _)| *key == accent) {
+            return format!("{}{}", letter, combining);
+        }
+    }
+    typed.to_string()
+}
+
+// Words end at a space or, in code, at a line break
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme == " " || grapheme == "\n"
+}
+
+// Grey for text, code keeps its highlighting until it is typed. Graphemes without a token are plain
+fn untyped_styles(tokens: &[TokenKind], len: usize) -> Vec<Style> {
+    (0..len)
+        .map(|i| match tokens.get(i).copied().unwrap_or(TokenKind::Plain) {
+            TokenKind::Plain => Style::default().fg(Color::DarkGray),
+            TokenKind::Keyword => Style::default().fg(Color::Magenta),
+            TokenKind::String => Style::default().fg(Color::Cyan),
+            TokenKind::Number => Style::default().fg(Color::Blue),
+            TokenKind::Comment => Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        })
+        .collect()
+}
+
+fn is_dead_key(typed: &str) -> bool {
+    let mut chars = typed.chars();
+    match (chars.next(), chars.next()) {
+        (Some(accent), None) => DEAD_KEYS.iter().any(|(key, _)| *key == accent),
+        _ => false,
+    }
+}
+
+// Lone dead key whose accent appears in the (decomposed) target
+fn is_dead_key_for(typed: &str, target: &str) -> bool {
+    let mut chars = typed.chars();
+    match (chars.next(), chars.next()) {
+        (Some(accent), None) => DEAD_KEYS
+            .iter()
+            .any(|(key, combining)| *key == accent && target.contains(*combining)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::languages::LanguageRegistry;
+    use crate::text_gen::BASE_PATH;
+
+    fn language(name: &str) -> LanguageInfo {
+        LanguageRegistry::discover_in(&[PathBuf::from(BASE_PATH)]).resolve(name)
+    }
+
+    fn new_test() -> TypingTest {
+        let options = AppOptions {
+            seed: Some(7),
+            ..AppOptions::default()
+        };
+        TypingTest::new(&options, &language("english"), &KeyStats::default())
+    }
+
+    // a test of exactly `text`, generated text is random and rarely has the case that is tested
+    fn test_with_text(text: &str, options: &AppOptions, language_name: &str) -> TypingTest {
+        let mut test = TypingTest::new(options, &language(language_name), &KeyStats::default());
+        test.target_text = text.to_string();
+        test.target_graphemes = text.graphemes(true).map(String::from).collect();
+        test.untyped_styles = untyped_styles(&[], test.target_graphemes.len());
+        test.colored_chars = test
+            .target_graphemes
+            .iter()
+            .cloned()
+            .zip(test.untyped_styles.iter().copied())
+            .collect();
+        test
+    }
+
+    fn type_text(test: &mut TypingTest, text: &str) {
+        text.chars().for_each(|c| test.type_char(c));
+    }
+
+    // the first two words with the space after each
+    fn two_words(test: &TypingTest) -> Vec<char> {
+        let second_space = test.target_text.match_indices(' ').nth(1).unwrap().0;
+        test.target_text[..=second_space].chars().collect()
+    }
+
+    #[test]
+    fn retyping_a_space_after_backspace_scores_the_word_once() {
+        let mut straight = new_test();
+        let text = two_words(&straight);
+        text.iter().for_each(|c| straight.type_char(*c));
+
+        let mut retyped = new_test();
+        let first_space = text.iter().position(|c| *c == ' ').unwrap();
+        text[..=first_space].iter().for_each(|c| retyped.type_char(*c));
+        retyped.backspace();
+        assert_eq!(retyped.last_word_start, 0);
+        text[first_space..].iter().for_each(|c| retyped.type_char(*c));
+
+        assert_eq!(retyped.index, straight.index);
+        assert_eq!(retyped.last_word_start, retyped.index);
+        assert_eq!(retyped.correct_words_chars, straight.correct_words_chars);
+    }
+
+    #[test]
+    fn composed_and_decomposed_input_match_either_target() {
+        for target in ["caf\u{e9}", "cafe\u{301}"] {
+            for typed in ["caf\u{e9}", "cafe\u{301}"] {
+                let mut test = test_with_text(target, &AppOptions::default(), "english");
+                type_text(&mut test, typed);
+                assert!(test.is_text_complete(), "{:?} for {:?}", typed, target);
+                assert_eq!(test.mistakes, 0, "{:?} for {:?}", typed, target);
+                assert_eq!(test.user_input[3], test.target_graphemes[3]);
+            }
+        }
+    }
+
+    #[test]
+    fn dead_key_and_letter_type_the_accented_letter() {
+        let mut test = test_with_text("\u{e9}t\u{e9}", &AppOptions::default(), "english");
+        type_text(&mut test, "\u{b4}e");
+        assert_eq!(test.index, 1);
+        assert_eq!(test.mistakes, 0);
+
+        // a dead key on the last grapheme waits for its letter instead of being committed
+        type_text(&mut test, "t\u{b4}");
+        assert_eq!(test.index, 2);
+        assert!(!test.is_text_complete());
+        type_text(&mut test, "e");
+        assert!(test.is_text_complete());
+        assert_eq!(test.mistakes, 0);
+    }
+
+    #[test]
+    fn unfinished_last_grapheme_is_committed() {
+        let mut test = test_with_text("caf\u{e9}", &AppOptions::default(), "english");
+        type_text(&mut test, "cafe");
+        assert!(!test.is_text_complete());
+        test.resolve_pending();
+        assert!(test.is_text_complete());
+        assert_eq!(test.mistakes, 1);
+    }
+
+    #[test]
+    fn letter_without_accent_is_committed_before_the_next_char() {
+        let lenient = AppOptions {
+            lenient_accents: true,
+            ..AppOptions::default()
+        };
+
+        // "a" could still become "ae", the "r" commits it and is typed against the next grapheme
+        let mut test = test_with_text("b\u{e4}r", &lenient, "german");
+        type_text(&mut test, "bar");
+        assert!(test.is_text_complete());
+        assert_eq!(test.accent_slips, 1);
+        assert_eq!(test.mistakes, 0);
+        assert_eq!(test.user_input, ["b", "\u{e4}", "r"]);
+
+        let mut test = test_with_text("b\u{e4}r", &lenient, "german");
+        type_text(&mut test, "baer");
+        assert!(test.is_text_complete());
+        assert_eq!(test.accent_slips, 1);
+        assert_eq!(test.mistakes, 0);
+
+        let mut test = test_with_text("b\u{e4}r", &AppOptions::default(), "german");
+        type_text(&mut test, "bar");
+        assert!(test.is_text_complete());
+        assert_eq!(test.mistakes, 1);
+        assert_eq!(test.user_input, ["b", "a", "r"]);
+    }
+
+    #[test]
+    fn space_skips_to_the_next_code_line() {
+        let code = AppOptions {
+            test_type: TestType::Code,
+            code_auto_indent: true,
+            ..AppOptions::default()
+        };
+        let mut test = test_with_text("let ab\n    cd", &code, "english");
+        type_text(&mut test, "let a ");
+        assert_eq!(test.mistakes, 0);
+        assert_eq!(test.missed_chars, 1);
+        assert_eq!(test.target_graphemes[test.index], "c");
+    }
+
+    #[test]
+    fn space_on_an_untouched_word_is_ignored() {
+        let mut test = test_with_text("ab cd", &AppOptions::default(), "english");
+        type_text(&mut test, " ab  ");
+        assert_eq!(test.index, 3);
+        assert_eq!(test.missed_chars, 0);
+        assert_eq!(test.mistakes, 0);
+    }
+
+    fn with_backspace(backspace_policy: BackspacePolicy) -> AppOptions {
+        AppOptions {
+            backspace_policy,
+            ..AppOptions::default()
+        }
+    }
+
+    #[test]
+    fn delete_word_removes_what_was_typed_of_the_word() {
+        for policy in [BackspacePolicy::Free, BackspacePolicy::WithinWord] {
+            let mut test = test_with_text("ab cde fg", &with_backspace(policy), "english");
+            type_text(&mut test, "ab cx");
+            test.delete_word();
+            assert_eq!(test.index, 3, "{}", policy);
+            assert_eq!(test.user_input, ["a", "b", " "], "{}", policy);
+            assert_eq!(test.colored_chars[3].1, test.untyped_styles[3], "{}", policy);
+        }
+    }
+
+    #[test]
+    fn delete_word_removes_extra_letters() {
+        for policy in [BackspacePolicy::Free, BackspacePolicy::WithinWord] {
+            let mut test = test_with_text("ab cd", &with_backspace(policy), "english");
+            type_text(&mut test, "abxy");
+            assert_eq!(test.extra_chars[&2], ["x", "y"]);
+            test.delete_word();
+            assert_eq!(test.index, 0, "{}", policy);
+            assert!(test.extra_chars.is_empty(), "{}", policy);
+            assert!(test.user_input.is_empty(), "{}", policy);
+        }
+    }
+
+    #[test]
+    fn delete_word_after_a_space_removes_the_previous_word() {
+        let mut test = test_with_text("ab cd", &with_backspace(BackspacePolicy::Free), "english");
+        type_text(&mut test, "ab ");
+        test.delete_word();
+        assert_eq!(test.index, 0);
+        assert!(test.user_input.is_empty());
+
+        // the previous word is finished, it stays as it is
+        let mut test = test_with_text("ab cd", &with_backspace(BackspacePolicy::WithinWord), "english");
+        type_text(&mut test, "ab ");
+        test.delete_word();
+        assert_eq!(test.index, 3);
+        assert_eq!(test.user_input, ["a", "b", " "]);
+    }
+
+    #[test]
+    fn within_word_backspace_stops_at_the_word_start() {
+        let mut test = test_with_text("ab cd", &with_backspace(BackspacePolicy::WithinWord), "english");
+        type_text(&mut test, "ab cx");
+        test.backspace();
+        test.backspace();
+        assert_eq!(test.index, 3);
+        test.backspace();
+        assert_eq!(test.index, 3);
+        assert_eq!(test.user_input, ["a", "b", " "]);
+    }
+
+    #[test]
+    fn disabled_backspace_changes_nothing() {
+        let mut test = test_with_text("ab cd ef", &with_backspace(BackspacePolicy::Disabled), "english");
+        type_text(&mut test, "ab cxyz");
+        let user_input = test.user_input.clone();
+        let mistakes = test.mistakes;
+
+        test.backspace();
+        test.delete_word();
+        assert_eq!(test.index, 5);
+        assert_eq!(test.user_input, user_input);
+        assert_eq!(test.extra_chars[&5], ["y", "z"]);
+        assert_eq!(test.mistakes, mistakes);
+    }
+
+    #[test]
+    fn reopened_word_stops_counting_until_it_is_finished() {
+        let mut test = test_with_text("ab cd ef", &with_backspace(BackspacePolicy::Free), "english");
+        type_text(&mut test, "ab ");
+        let scored = test.correct_words_chars;
+        assert!(scored > 0);
+
+        test.backspace();
+        assert_eq!(test.correct_words_chars, 0);
+        type_text(&mut test, " ");
+        assert_eq!(test.correct_words_chars, scored);
+    }
+}
+
@@ -0,0 +1,208 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Row, Sparkline, Table, TableState},
+    Frame,
+};
+
+use crate::key_stats::KeyStats;
+use crate::languages::display_name;
+use crate::results::{ProfileStats, TestResult};
+
+// how many of the latest results are shown in the accuracy trend
+const ACCURACY_TREND_LEN: usize = 60;
+// how many of the worst chars and bigrams are listed
+const WEAK_KEYS_LEN: usize = 5;
+
+pub fn draw_account_tab(
+    frame: &mut Frame,
+    area: Rect,
+    result_history: &[TestResult],
+    tests_started: usize,
+    key_stats: &KeyStats,
+    table_state: &mut TableState,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(8),      // summary
+            Constraint::Percentage(35), // modes + accuracy trend
+            Constraint::Min(0),         // recent results
+        ])
+        .split(area);
+
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+
+    let stats = ProfileStats::from_results(result_history, tests_started);
+
+    frame.render_widget(create_summary(&stats, key_stats), chunks[0]);
+    frame.render_widget(create_mode_table(&stats), middle[0]);
+    draw_accuracy_trend(frame, middle[1], result_history);
+    draw_recent_results(frame, chunks[2], result_history, table_state);
+}
+
+fn create_summary(stats: &ProfileStats, key_stats: &KeyStats) -> Paragraph<'static> {
+    let total_secs = stats.total_time_secs as u64;
+    let hours = total_secs / 3600;
+    let minutes = (total_secs / 60) % 60;
+    let seconds = total_secs % 60;
+
+    let best_wpm = stats.modes.iter().map(|mode| mode.best_wpm).max();
+
+    let summary_text = vec![
+        Line::from(format!("Tests started: {}", stats.tests_started)),
+        Line::from(format!("Tests completed: {}", stats.tests_completed)),
+        Line::from(format!("Time typed: {}:{:02}:{:02}", hours, minutes, seconds)),
+        Line::from(format!(
+            "Best wpm: {}",
+            best_wpm.map_or(String::from("-"), |wpm| wpm.to_string())
+        )),
+        Line::from(format!(
+            "Weakest keys: {}",
+            format_weak_keys(&key_stats.worst_chars(WEAK_KEYS_LEN))
+        )),
+        Line::from(format!(
+            "Weakest bigrams: {}",
+            format_weak_keys(&key_stats.worst_bigrams(WEAK_KEYS_LEN))
+        )),
+    ];
+
+    Paragraph::new(summary_text)
+        .block(Block::default().borders(Borders::ALL).title("Profile"))
+}
+
+// "e 12%, r 8%" or "-" if nothing was missed yet
+fn format_weak_keys(keys: &[(&str, f64)]) -> String {
+    if keys.is_empty() {
+        return String::from("-");
+    }
+    keys.iter()
+        .map(|(key, rate)| format!("{} {:.0}%", key, rate * 100.0))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn create_mode_table(stats: &ProfileStats) -> Table<'static> {
+    let rows: Vec<Row> = stats
+        .modes
+        .iter()
+        .map(|mode| {
+            Row::new(vec![
+                mode.mode.clone(),
+                mode.tests.to_string(),
+                format!("{:.1}", mode.average_wpm),
+                mode.best_wpm.to_string(),
+            ])
+        })
+        .collect();
+
+    Table::new(
+        rows,
+        [
+            Constraint::Min(12),
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(vec!["Mode", "Tests", "Avg wpm", "Best wpm"])
+            .style(Style::default().fg(Color::Yellow)),
+    )
+    .block(Block::default().borders(Borders::ALL).title("Per mode"))
+}
+
+fn draw_accuracy_trend(frame: &mut Frame, area: Rect, result_history: &[TestResult]) {
+    // only the latest results that fit into the box
+    let width = (area.width.saturating_sub(2) as usize).min(ACCURACY_TREND_LEN);
+    let accuracies: Vec<u64> = result_history
+        .iter()
+        .rev()
+        .take(width)
+        .rev()
+        .map(|result| result.accuracy.round() as u64)
+        .collect();
+
+    let average = if accuracies.is_empty() {
+        0.0
+    } else {
+        accuracies.iter().sum::<u64>() as f64 / accuracies.len() as f64
+    };
+
+    let sparkline = Sparkline::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Accuracy trend (avg {:.1}%)", average)),
+        )
+        .data(&accuracies)
+        .max(100)
+        .style(Style::default().fg(Color::Green));
+
+    frame.render_widget(sparkline, area);
+}
+
+fn draw_recent_results(
+    frame: &mut Frame,
+    area: Rect,
+    result_history: &[TestResult],
+    table_state: &mut TableState,
+) {
+    // newest first
+    let rows: Vec<Row> = result_history
+        .iter()
+        .rev()
+        .map(|result| {
+            let status_style = if result.completed {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::LightRed)
+            };
+            Row::new(vec![
+                result.timestamp.format("%Y-%m-%d %H:%M").to_string(),
+                display_name(&result.language),
+                result.test_type.to_string(),
+                result.settings_label(),
+                result.wpm.to_string(),
+                result.wpm_raw.to_string(),
+                format!("{:.1}%", result.accuracy),
+                result.mistakes.to_string(),
+                if result.completed { "completed" } else { "failed" }.to_string(),
+            ])
+            .style(status_style)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(16),
+            Constraint::Length(8),
+            Constraint::Min(12),
+            Constraint::Min(10),
+            Constraint::Length(5),
+            Constraint::Length(5),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Date", "Language", "Type", "Mode", "Wpm", "Raw", "Acc", "Mistakes", "Status",
+        ])
+        .style(Style::default().fg(Color::Yellow)),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Recent results (j/k to scroll)"),
+    );
+
+    frame.render_stateful_widget(table, area, table_state);
+}
@@ -4,10 +4,28 @@ use crate::type_test::TestDataPerSecond;
 
 
 
+/// Two points spanning the whole chart at the height of the personal best, empty without a pb.
+pub fn pb_line_points(
+    test_data_history: &[TestDataPerSecond],
+    personal_best: Option<i64>,
+) -> Vec<(f64, f64)> {
+    let max_time = test_data_history
+        .iter()
+        .map(|data| data.timestamp)
+        .max()
+        .unwrap_or(0);
+
+    match personal_best {
+        Some(pb) => vec![(0.0, pb as f64), (max_time as f64, pb as f64)],
+        None => Vec::new(),
+    }
+}
+
 pub fn create_chart<'a>(
     test_data_history: &'a [TestDataPerSecond],
     wpm_points: &'a [(f64, f64)],
     wpm_raw_points: &'a [(f64, f64)],
+    pb_points: &'a [(f64, f64)],
 ) -> Chart<'a> {
     //get the maximum out of the y values
     // Iterator for y-Values
@@ -21,8 +39,13 @@ pub fn create_chart<'a>(
         .map(|&(_, y)| y)
         .fold(f64::NEG_INFINITY, f64::max);
 
+    let max_y_pb = pb_points
+        .iter()
+        .map(|&(_, y)| y)
+        .fold(f64::NEG_INFINITY, f64::max);
+
     // compare maxima
-    let max_y = max_y_points.max(max_y_raw_points);
+    let max_y = max_y_points.max(max_y_raw_points).max(max_y_pb);
 
     // Find exact maximum time
     let max_time = test_data_history
@@ -45,7 +68,19 @@ pub fn create_chart<'a>(
         .graph_type(GraphType::Line)
         .data(wpm_raw_points);
 
-    Chart::new(vec![wpm_dataset, wpm_raw_dataset])
+    let mut datasets = vec![wpm_dataset, wpm_raw_dataset];
+    if !pb_points.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name("PB")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Magenta))
+                .graph_type(GraphType::Line)
+                .data(pb_points),
+        );
+    }
+
+    Chart::new(datasets)
         .block(Block::bordered().title("WPM"))
         .x_axis(
             Axis::default()
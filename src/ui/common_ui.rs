@@ -2,52 +2,183 @@ use ratatui::{
     style::{Color, Style},
     text::{Line, Span},
 };
-use textwrap::{wrap, Options, WordSplitter};
-
-pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
-    let options = Options::new(width)
-        .word_separator(textwrap::WordSeparator::AsciiSpace)
-        .word_splitter(WordSplitter::NoHyphenation)
-        .break_words(false);
-    wrap(text, options)
-        .into_iter()
-        .map(|s| s.into_owned())
-        .collect()
+use std::collections::BTreeMap;
+use std::ops::Range;
+use unicode_width::UnicodeWidthStr;
+
+/// The target text broken into lines that fit the goal text box. Lines are ranges of grapheme
+/// indices, so text appended in a time race only wraps its new part. Kept between frames for one test.
+#[derive(Default)]
+pub struct WrappedLines {
+    width: usize,
+    keep_line_breaks: bool, // code breaks at its own line breaks, prose only at spaces
+    lines: Vec<Range<usize>>,
+    extra_widths: BTreeMap<usize, usize>, // columns of the extra letters at the last update
+}
+
+impl WrappedLines {
+    pub fn new(keep_line_breaks: bool) -> Self {
+        Self {
+            keep_line_breaks,
+            ..Self::default()
+        }
+    }
+
+    /// Bring the lines up to date with the text, only the last line and what was appended are wrapped again.
+    /// A new width wraps everything, changed extra letters everything from their line on.
+    /// `extras` are letters typed past a word, shown before the grapheme they are keyed with.
+    pub fn update(&mut self, text: &[(String, Style)], extras: &BTreeMap<usize, Vec<String>>, width: usize) {
+        let width = width.max(1);
+        if width != self.width {
+            self.width = width;
+            self.lines.clear();
+        }
+
+        let extra_widths: BTreeMap<usize, usize> =
+            extras.iter().map(|(i, extra)| (*i, extra.concat().width())).collect();
+        let changed = extra_widths
+            .keys()
+            .chain(self.extra_widths.keys())
+            .filter(|i| extra_widths.get(i) != self.extra_widths.get(i))
+            .min();
+        if let Some(index) = changed {
+            self.invalidate_from(*index);
+        }
+        self.extra_widths = extra_widths;
+
+        // the last line may get more words, all lines before it are final
+        let start = self.lines.pop().map_or(0, |last| last.start);
+        if self.keep_line_breaks {
+            self.wrap_code(text, extras, start);
+        } else {
+            self.wrap_prose(text, extras, start);
+        }
+    }
+
+    // Wrap again from the line before the one with `index`, extra letters there can push
+    // a word to the next line or, once removed, let it move back
+    fn invalidate_from(&mut self, index: usize) {
+        let line = self.line_of_index(index).saturating_sub(1);
+        self.lines.truncate(line + 1);
+    }
+
+    pub fn lines(&self) -> &[Range<usize>] {
+        &self.lines
+    }
+
+    /// Line that contains the grapheme at `index`.
+    pub fn line_of_index(&self, index: usize) -> usize {
+        self.lines
+            .partition_point(|line| line.end <= index)
+            .min(self.lines.len().saturating_sub(1))
+    }
+
+    // Break before a word that doesn't fit anymore, the space after a word stays on its line
+    fn wrap_prose(&mut self, text: &[(String, Style)], extras: &BTreeMap<usize, Vec<String>>, start: usize) {
+        let mut line_start = start;
+        let mut line_width = 0;
+        let mut word_start = start;
+
+        while word_start < text.len() {
+            let word_end = text[word_start..]
+                .iter()
+                .position(|(g, _)| g == " ")
+                .map_or(text.len(), |i| word_start + i + 1);
+            let word_width: usize = (word_start..word_end).map(|i| display_width(text, extras, i)).sum();
+            let space_width = if text[word_end - 1].0 == " " { 1 } else { 0 };
+
+            if line_width > 0 && line_width + word_width - space_width > self.width {
+                self.lines.push(line_start..word_start);
+                line_start = word_start;
+                line_width = 0;
+            }
+
+            if word_width - space_width > self.width {
+                // a word longer than the whole box is cut into pieces
+                for (i, (g, _)) in text[word_start..word_end].iter().enumerate() {
+                    let width = display_width(text, extras, word_start + i);
+                    if line_width > 0 && line_width + width > self.width && g != " " {
+                        self.lines.push(line_start..word_start + i);
+                        line_start = word_start + i;
+                        line_width = 0;
+                    }
+                    line_width += width;
+                }
+            } else {
+                line_width += word_width;
+            }
+            word_start = word_end;
+        }
+
+        if line_start < text.len() {
+            self.lines.push(line_start..text.len());
+        }
+    }
+
+    // Break after every line break and cut lines that are too long
+    fn wrap_code(&mut self, text: &[(String, Style)], extras: &BTreeMap<usize, Vec<String>>, start: usize) {
+        let mut line_start = start;
+        let mut line_width = 0;
+
+        for (i, (g, _)) in text.iter().enumerate().skip(start) {
+            let width = display_width(text, extras, i);
+            if line_width > 0 && line_width + width > self.width {
+                self.lines.push(line_start..i);
+                line_start = i;
+                line_width = 0;
+            }
+            line_width += width;
+
+            if g == "\n" {
+                self.lines.push(line_start..i + 1);
+                line_start = i + 1;
+                line_width = 0;
+            }
+        }
+
+        if line_start < text.len() {
+            self.lines.push(line_start..text.len());
+        }
+    }
+}
+
+// a line break is shown as a symbol, so it takes one column
+fn grapheme_width(grapheme: &str) -> usize {
+    if grapheme == "\n" {
+        1
+    } else {
+        grapheme.width()
+    }
+}
+
+// columns of the grapheme at `index` together with the extra letters shown before it
+fn display_width(text: &[(String, Style)], extras: &BTreeMap<usize, Vec<String>>, index: usize) -> usize {
+    grapheme_width(&text[index].0) + extras.get(&index).map_or(0, |extra| extra.concat().width())
 }
 
-pub  fn create_colored_text<'a>(
-    wrapped_text: &'a [String],
-    colored_chars: &'a [(char, Style)],
+pub fn create_colored_text<'a>(
+    colored_chars: &'a [(String, Style)],
+    extras: &'a BTreeMap<usize, Vec<String>>,
+    lines: &[Range<usize>],
     current_index: usize,
 ) -> Vec<Line<'a>> {
     let mut colored_text: Vec<Line> = Vec::new();
-    let mut char_index = 0;
 
-    for (line_index, line) in wrapped_text.iter().enumerate() {
+    for line in lines {
         let mut spans: Vec<Span> = Vec::new();
 
-        for c in line.chars() {
-            let style = if char_index == current_index {
-                colored_chars[char_index]
-                    .1
-                    .clone()
-                    .bg(Color::Yellow)
-                    .fg(Color::Black)
+        for (char_index, (g, style)) in colored_chars[line.clone()].iter().enumerate() {
+            if let Some(extra) = extras.get(&(line.start + char_index)) {
+                spans.extend(extra.iter().map(|e| Span::styled(e.as_str(), Style::default().fg(Color::Red))));
+            }
+            let style = if line.start + char_index == current_index {
+                style.bg(Color::Yellow).fg(Color::Black)
             } else {
-                colored_chars[char_index].1
+                *style
             };
-            spans.push(Span::styled(c.to_string(), style));
-            char_index += 1;
-        }
-
-        if line_index < wrapped_text.len() - 1 {
-            let space_style = if char_index == current_index {
-                Style::default().bg(Color::Yellow).fg(Color::Black)
-            } else {
-                Style::default().fg(Color::DarkGray)
-            };
-            spans.push(Span::styled(" ", space_style));
-            char_index += 1;
+            // a line break has no width, show a symbol so it can be typed like any other char
+            let shown = if g == "\n" { "↵" } else { g.as_str() };
+            spans.push(Span::styled(shown, style));
         }
 
         colored_text.push(Line::from(spans));
@@ -55,3 +186,30 @@ pub  fn create_colored_text<'a>(
 
     colored_text
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Vec<(String, Style)> {
+        s.chars().map(|c| (c.to_string(), Style::default())).collect()
+    }
+
+    #[test]
+    fn extra_letters_push_a_word_down_and_back() {
+        let text = text("aaa bbb ccc");
+        let mut wrapped = WrappedLines::new(false);
+        let mut extras = BTreeMap::new();
+
+        wrapped.update(&text, &extras, 8);
+        assert_eq!(wrapped.lines(), &[0..8, 8..11]);
+
+        extras.insert(3, vec!["x".to_string(), "y".to_string()]);
+        wrapped.update(&text, &extras, 8);
+        assert_eq!(wrapped.lines(), &[0..4, 4..11]);
+
+        extras.clear();
+        wrapped.update(&text, &extras, 8);
+        assert_eq!(wrapped.lines(), &[0..8, 8..11]);
+    }
+}
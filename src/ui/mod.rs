@@ -1,42 +1,48 @@
 mod typing_ui;
 // mod options;
-// mod account;
+mod account_ui;
 // mod about;
 mod chart;
 mod common_ui;
 mod layout_ui;
 pub mod tabs;
 
-use ratatui::{style::{Color, Style}, text::{Line, Span}, widgets::{Block, Borders, Paragraph}, Frame};
+use ratatui::{style::{Color, Style}, text::{Line, Span}, widgets::{Block, Borders, Paragraph, TableState}, Frame};
 use crate::{app::{AppState, OptionsState}, app_options::AppOptions};
+use crate::key_stats::KeyStats;
+use crate::results::TestResult;
 use crate::type_test::TypingTest;
 
-pub use chart::create_chart;
-pub use common_ui::{create_colored_text, wrap_text};
+pub use chart::{create_chart, pb_line_points};
+pub use common_ui::{create_colored_text, WrappedLines};
 use tabs::{SelectedTab,draw_tabs,draw_options};
 use layout_ui::create_main_layout;
 
 
-pub fn draw_ui(frame: &mut Frame, app_state: &AppState, selected_tab: &SelectedTab, typing_test: &TypingTest,options: &AppOptions, options_state:&OptionsState) {
+/// Stored results and stats, borrowed from the app for one frame.
+pub struct ProfileView<'a> {
+    pub personal_best: Option<i64>, // best wpm for the current test settings
+    pub result_history: &'a [TestResult],
+    pub tests_started: usize,
+    pub key_stats: &'a KeyStats,
+    pub account_table_state: &'a mut TableState,
+}
+
+/// The running test and the lines its goal text is wrapped into, borrowed from the app for one frame.
+pub struct TypingView<'a> {
+    pub typing_test: &'a TypingTest,
+    pub wrapped: &'a mut WrappedLines,
+}
+
+pub fn draw_ui(frame: &mut Frame, app_state: &AppState, selected_tab: &SelectedTab, typing: TypingView,options: &AppOptions, options_state:&OptionsState, profile: ProfileView) {
     let main_layout = create_main_layout(frame);
     
     draw_tabs(frame, main_layout[0], selected_tab);
     
     match selected_tab {
-        SelectedTab::Tab1 => typing_ui::draw_typing_tab(frame, typing_test, app_state, options),
+        SelectedTab::Tab1 => typing_ui::draw_typing_tab(frame, typing.typing_test, typing.wrapped, app_state, options, profile.personal_best),
         SelectedTab::Tab2 => draw_options(frame, &main_layout, options,options_state),
-        SelectedTab::Tab3 => {
-            // Account Tab
-            let account_text = vec![
-                Line::from("Account"),
-                Line::from("Coming Soon..."),
-            ];
-            frame.render_widget(
-                Paragraph::new(account_text)
-                    .block(Block::default().borders(Borders::ALL).title("Account")),
-                main_layout[1],
-            );
-        },
+        SelectedTab::Tab3 => account_ui::draw_account_tab(frame, main_layout[1], profile.result_history, profile.tests_started, profile.key_stats, profile.account_table_state),
         SelectedTab::Tab4 => {
             // About Tab
             let about_text = vec![
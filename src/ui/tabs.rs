@@ -3,7 +3,7 @@ use ratatui::{
     layout::Rect, style::{Color, Style, Stylize}, text::{Line, Span}, widgets::{Block, Borders, Paragraph, Tabs}, Frame
 };
 
-use crate::{app::OptionsState, app_options::AppOptions};
+use crate::{app::OptionsState, app_options::AppOptions, custom_text::custom_text_label, languages::display_name};
 
 #[derive(Default, Clone, Copy, Display, FromRepr, EnumIter)]
 pub enum SelectedTab {
@@ -57,14 +57,51 @@ pub fn draw_tabs(frame: &mut Frame, area: Rect, selected_tab: &SelectedTab) {
 
 pub fn draw_options(frame: &mut Frame<'_>, main_layout: &Vec<Rect>, options: &AppOptions, options_state: &OptionsState) {
     let options_content = vec![
-        (format!("Test Language: {}", options.test_language.to_string()), 0),
-        (format!("Test Type: {}", options.test_type.to_string()), 1),
-        (format!("Timed Race: {}", options.time_race_enabled.then_some("enabled").unwrap_or("disabled")), 2),
-        (format!("Hardcore: {}", options.hardcore_enabled.then_some("enabled").unwrap_or("disabled")), 3),
-        (format!("(WIP) UI Language: {}", options.ui_language.to_string()), 4),
+        (format!("Test Language: {}", display_name(&options.test_language)), 0),
+        (format!("Test Type: {}", options.test_type), 1),
+        (format!("Quote Length: {}", options.quote_length), 2),
+        (format!("Test Mode: {}", options.test_mode), 3),
+        (format!("Word Count: {}", options.word_count), 4),
+        (format!("Race Duration: {}", options.time_race_duration), 5),
+        (format!("Custom Duration: {}s", options.custom_time_secs), 6),
+        (format!("Hardcore: {}", if options.hardcore_enabled { "enabled" } else { "disabled" }), 7),
+        (format!(
+            "Lazy Mode ({}): {}",
+            display_name(&options.test_language),
+            if options.is_lazy(&options.test_language) { "enabled" } else { "disabled" }
+        ), 8),
+        (format!("Lenient Accents: {}", if options.lenient_accents { "enabled" } else { "disabled" }), 9),
+        (format!("Custom Text: {}", custom_text_label(options.custom_text_path.as_deref())), 10),
+        (format!("Custom Text Mode: {}", options.custom_text_mode), 11),
+        (format!("Custom Lowercase: {}", if options.custom_lowercase { "enabled" } else { "disabled" }), 12),
+        (format!(
+            "Custom Strip Punctuation: {}",
+            if options.custom_strip_punctuation { "enabled" } else { "disabled" }
+        ), 13),
+        (format!("Punctuation: {}", options.punctuation), 14),
+        (format!("Numbers: {}%", options.numbers_percent), 15),
+        (format!(
+            "Word Sampling: {}{}",
+            options.word_sampling,
+            if options.zipf_exponent().is_some() && options_state.zipf_unsupported {
+                " (no effect, this word list is not ordered by frequency)"
+            } else {
+                ""
+            }
+        ), 16),
+        (format!("Zipf Exponent: {:.1}", options.zipf_exponent), 17),
+        (format!("Code Language: {}", display_name(&options.code_language)), 18),
+        (format!("Code Auto Indent: {}", if options.code_auto_indent { "enabled" } else { "disabled" }), 19),
+        (format!(
+            "Seed: {}",
+            options.seed.map_or(String::from("random"), |seed| seed.to_string())
+        ), 20),
+        (format!("Backspace: {}", options.backspace_policy), 21),
+        (format!("Ctrl+H Deletes Word: {}", if options.ctrl_h_deletes_word { "enabled" } else { "disabled" }), 22),
+        (format!("(WIP) UI Language: {}", options.ui_language), 23),
     ];
 
-    let options_text: Vec<Line> = options_content
+    let mut options_text: Vec<Line> = options_content
         .into_iter()
         .map(|(text, index)| {
             if index == options_state.selected_option {
@@ -78,8 +115,21 @@ pub fn draw_options(frame: &mut Frame<'_>, main_layout: &Vec<Rect>, options: &Ap
         })
         .collect();
 
+    options_text.push(Line::from(""));
+    if let Some(message) = &options_state.message {
+        options_text.push(Line::from(message.clone()).fg(Color::LightRed));
+    }
+    options_text.push(Line::from(
+        "Paste text here to type it as custom text, files go into the texts data directory",
+    ).fg(Color::DarkGray));
+
+    // scroll so the selected option stays visible on small terminals
+    let visible_lines = main_layout[1].height.saturating_sub(2) as usize;
+    let scroll = (options_state.selected_option + 1).saturating_sub(visible_lines) as u16;
+
     frame.render_widget(
         Paragraph::new(options_text)
+            .scroll((scroll, 0))
             .block(Block::default().borders(Borders::ALL).title("Options")),
         main_layout[1],
     );
@@ -6,23 +6,35 @@ use ratatui::{
     Frame,
 };
 
-use crate::{app::AppState, app_options::AppOptions, type_test::TypingTest};
+use crate::{
+    app::AppState,
+    app_options::AppOptions,
+    type_test::TypingTest,
+};
 
-use super::{create_chart, create_colored_text, wrap_text};
+use super::{create_chart, create_colored_text, pb_line_points, WrappedLines};
 
 pub fn draw_typing_tab(
     frame: &mut Frame,
     typing_test: &TypingTest,
+    wrapped: &mut WrappedLines,
     app_state: &AppState,
     options: &AppOptions,
+    personal_best: Option<i64>,
 ) {
     match app_state {
-        AppState::EndScreen => draw_end_screen(frame, typing_test),
-        _ => draw_typing_screen(frame, typing_test, options),
+        AppState::EndScreen => draw_end_screen(frame, typing_test, personal_best),
+        _ => draw_typing_screen(frame, typing_test, wrapped, options, personal_best),
     }
 }
 
-pub fn draw_typing_screen(frame: &mut Frame, typing_test: &TypingTest, options: &AppOptions) {
+pub fn draw_typing_screen(
+    frame: &mut Frame,
+    typing_test: &TypingTest,
+    wrapped: &mut WrappedLines,
+    options: &AppOptions,
+    personal_best: Option<i64>,
+) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -36,9 +48,17 @@ pub fn draw_typing_screen(frame: &mut Frame, typing_test: &TypingTest, options:
 
     // Goal Text
     let available_width = chunks[1].width as usize - 4;
-    let wrapped_text = wrap_text(&typing_test.target_text, available_width);
-    let colored_text =
-        create_colored_text(&wrapped_text, &typing_test.colored_chars, typing_test.index);
+    wrapped.update(&typing_test.colored_chars, &typing_test.extra_chars, available_width);
+    // completed lines are dropped from the view, one stays above the cursor for context
+    let visible_lines = chunks[1].height.saturating_sub(2) as usize;
+    let first_line = wrapped.line_of_index(typing_test.index).saturating_sub(1);
+    let last_line = (first_line + visible_lines).min(wrapped.lines().len());
+    let colored_text = create_colored_text(
+        &typing_test.colored_chars,
+        &typing_test.extra_chars,
+        &wrapped.lines()[first_line..last_line],
+        typing_test.index,
+    );
     let target_text = Paragraph::new(colored_text)
         .block(Block::default().borders(Borders::ALL).title("Goal text"));
     frame.render_widget(target_text, chunks[1]);
@@ -56,24 +76,41 @@ pub fn draw_typing_screen(frame: &mut Frame, typing_test: &TypingTest, options:
         .map(|f| (f.timestamp as f64, f.wpm_raw as f64))
         .collect();
 
-    let chart = create_chart(&typing_test.test_data_history, &wpm_points, &wpm_raw_points);
+    let pb_points = pb_line_points(&typing_test.test_data_history, personal_best);
+
+    let chart = create_chart(
+        &typing_test.test_data_history,
+        &wpm_points,
+        &wpm_raw_points,
+        &pb_points,
+    );
     frame.render_widget(chart, chunks[2]);
 
-    // Progress Bar (Text Progress)
+    // Progress Bar (Words Progress)
     let progress = typing_test.progress();
+    let progress_label = if options.is_time_race() {
+        format!("{} words", typing_test.words_completed())
+    } else {
+        format!(
+            "{}/{} words",
+            typing_test.words_completed(),
+            typing_test.total_words()
+        )
+    };
     let gauge = Gauge::default()
         .block(Block::default().borders(Borders::ALL).title("Progress"))
         .gauge_style(Style::default().fg(Color::Cyan))
-        .percent(progress);
+        .percent(progress)
+        .label(progress_label);
     frame.render_widget(gauge, chunks[3]);
 
     // Time Race Bar (if activated)
-    if options.time_race_enabled {
-        const TIME_LIMIT: f64 = 30.0; // TODO: aus den Options holen
+    if options.is_time_race() {
+        let time_limit = options.time_limit_secs() as f64;
         let elapsed = typing_test.get_elapsed_time().as_secs_f64();
-        let remaining_time = (TIME_LIMIT - elapsed).max(0.0);
-        // let time_progress = ((remaining_time / TIME_LIMIT) * 100.0) as u16;
-        let time_progress = 100 - ((remaining_time / TIME_LIMIT) * 100.0) as u16;
+        let remaining_time = (time_limit - elapsed).max(0.0);
+        // let time_progress = ((remaining_time / time_limit) * 100.0) as u16;
+        let time_progress = 100 - ((remaining_time / time_limit) * 100.0) as u16;
 
         // Color based on time progress
         // let color = match time_progress {
@@ -101,18 +138,20 @@ pub fn draw_typing_screen(frame: &mut Frame, typing_test: &TypingTest, options:
     }
 }
 
-pub fn draw_end_screen(frame: &mut Frame, typing_test: &TypingTest) {
+pub fn draw_end_screen(frame: &mut Frame, typing_test: &TypingTest, personal_best: Option<i64>) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(2), // spacing after tabs
-            Constraint::Length(8), // stats
+            Constraint::Length(10), // stats
             Constraint::Min(0),    //chart
         ])
         .split(frame.area());
 
     let stats_text = if typing_test.text_finished {
-        create_test_stats_text(typing_test)
+        let mut stats_text = create_test_stats_text(typing_test);
+        stats_text.push(create_pb_text(typing_test.get_wpm(), personal_best));
+        stats_text
     } else {
         vec![
             Line::from(vec![Span::styled(
@@ -143,10 +182,31 @@ pub fn draw_end_screen(frame: &mut Frame, typing_test: &TypingTest) {
         .map(|f| (f.timestamp as f64, f.wpm_raw as f64))
         .collect();
 
-    let chart = create_chart(&typing_test.test_data_history, &wpm_points, &wpm_raw_points);
+    let pb_points = pb_line_points(&typing_test.test_data_history, personal_best);
+
+    let chart = create_chart(
+        &typing_test.test_data_history,
+        &wpm_points,
+        &wpm_raw_points,
+        &pb_points,
+    );
     frame.render_widget(chart, chunks[2]);
 }
 
+fn create_pb_text(wpm: i64, personal_best: Option<i64>) -> Line<'static> {
+    match personal_best {
+        None => Line::from(Span::styled(
+            "New PB! First result in this mode",
+            Style::default().fg(Color::Magenta),
+        )),
+        Some(pb) if wpm > pb => Line::from(Span::styled(
+            format!("New PB! +{} wpm over your previous best of {}", wpm - pb, pb),
+            Style::default().fg(Color::Magenta),
+        )),
+        Some(pb) => Line::from(format!("PB: {} wpm ({} behind)", pb, pb - wpm)),
+    }
+}
+
 fn create_test_stats_text(typing_test: &TypingTest) -> Vec<Line<'_>> {
     //accuracy text
     let accuracy_text = format!("Accuracy: {:.2}", typing_test.accuracy());
@@ -162,17 +222,42 @@ fn create_test_stats_text(typing_test: &TypingTest) -> Vec<Line<'_>> {
         typing_test.get_wpm_raw()
     );
     //errors text
-    let error_text = format!(
+    let mut error_text = format!(
         "Mistakes: {} out of {} total characters",
         typing_test.mistakes,
-        typing_test.target_text.len()
+        typing_test.text_len()
     );
+    if typing_test.missed_chars > 0 || typing_test.extra_chars_typed > 0 {
+        error_text.push_str(&format!(
+            " ({} missed, {} extra)",
+            typing_test.missed_chars, typing_test.extra_chars_typed
+        ));
+    }
 
-    let stats_text = vec![
+    let mut stats_text = vec![
         Line::from(accuracy_text),
         Line::from(time_text),
         Line::from(wpm_text),
         Line::from(error_text),
     ];
+    //accent slips, only counted in lenient accent mode
+    if typing_test.options().lenient_accents {
+        stats_text.push(Line::from(Span::styled(
+            format!("Accent slips: {}", typing_test.accent_slips),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    //quote source
+    if let Some(source) = &typing_test.source {
+        stats_text.push(Line::from(Span::styled(
+            format!("- {}", source),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    //seed, to race the same text again
+    stats_text.push(Line::from(Span::styled(
+        format!("Seed: {}", typing_test.seed),
+        Style::default().fg(Color::DarkGray),
+    )));
     stats_text
 }